@@ -0,0 +1,207 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use qrcode::bit::Bit;
+use qrcode::ec::{codewords, data_codewords_count, ec_codewords_per_block, EcLevel};
+use qrcode::encoder::Encoder;
+use qrcode::encoding::Encoding;
+use qrcode::mask::MaskPattern;
+use qrcode::preprocessor::Preprocessor;
+use qrcode::qrcode::QrCode;
+use qrcode::render::png::PngOptions;
+use qrcode::render::svg::SvgOptions;
+
+const VERSIONS: [u8; 4] = [1, 10, 25, 40];
+const EC_LEVEL: EcLevel = EcLevel::M;
+
+// Largest byte mode payload that still fits in the given version.
+fn byte_capacity(version: u8) -> usize {
+    let char_count_bits = if version < 10 { 8 } else { 16 };
+    (data_codewords_count(version, &EC_LEVEL) * 8 - 4 - char_count_bits) / 8
+}
+
+fn payload(version: u8, alphabet: &[u8]) -> String {
+    alphabet
+        .iter()
+        .cycle()
+        .take(byte_capacity(version))
+        .map(|&c| c as char)
+        .collect()
+}
+
+fn data_bytes(version: u8) -> Vec<u8> {
+    (0..data_codewords_count(version, &EC_LEVEL))
+        .map(|i| (i * 31 % 256) as u8)
+        .collect()
+}
+
+fn template(version: u8) -> QrCode {
+    let mut qrcode = QrCode::new(version, EC_LEVEL, MaskPattern::Checkerboard, Encoding::Byte)
+        .expect("valid version");
+    qrcode.all_functional_patterns();
+    qrcode
+}
+
+// Data and error correction codeword bits, in the order `fill` places them.
+fn codeword_bits(version: u8) -> Vec<Bit> {
+    let data = data_bytes(version);
+    let ec_bytes = ec_codewords_per_block(version, &EC_LEVEL);
+    let (data_codewords, ec_codewords) = codewords(&data, version, &EC_LEVEL, ec_bytes);
+
+    let mut bits = Bit::bits(&data_codewords, data_codewords.len() * 8);
+    bits.extend(Bit::bits(&ec_codewords, ec_codewords.len() * 8));
+    bits
+}
+
+fn filled(version: u8) -> QrCode {
+    let mut qrcode = template(version);
+    qrcode.fill(&codeword_bits(version));
+    qrcode
+}
+
+fn encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encoding");
+    for version in VERSIONS {
+        let numeric = payload(version, b"0123456789");
+        let alphanumeric = payload(version, b"HTTPS://EXAMPLE.COM/");
+        let byte = payload(version, b"https://example.com/");
+
+        group.bench_with_input(BenchmarkId::new("numeric", version), &numeric, |b, data| {
+            b.iter(|| Encoding::Numeric.encode(data))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("alphanumeric", version),
+            &alphanumeric,
+            |b, data| b.iter(|| Encoding::Alphanumeric.encode(data)),
+        );
+        group.bench_with_input(BenchmarkId::new("byte", version), &byte, |b, data| {
+            b.iter(|| Encoding::Byte.encode(data))
+        });
+    }
+    group.finish();
+}
+
+fn error_correction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("codewords");
+    for version in VERSIONS {
+        let data = data_bytes(version);
+        let ec_bytes = ec_codewords_per_block(version, &EC_LEVEL);
+        group.bench_with_input(BenchmarkId::from_parameter(version), &data, |b, data| {
+            b.iter(|| codewords(data, version, &EC_LEVEL, ec_bytes))
+        });
+    }
+    group.finish();
+}
+
+fn functional_patterns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("all_functional_patterns");
+    for version in VERSIONS {
+        group.bench_function(BenchmarkId::from_parameter(version), |b| {
+            b.iter(|| template(version))
+        });
+    }
+    group.finish();
+}
+
+fn fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fill");
+    for version in VERSIONS {
+        let bits = codeword_bits(version);
+        let template = template(version);
+        group.bench_function(BenchmarkId::from_parameter(version), |b| {
+            b.iter_batched(
+                || template.clone(),
+                |mut qrcode| {
+                    qrcode.fill(&bits);
+                    qrcode
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn mask(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply_mask");
+    for version in VERSIONS {
+        let qrcode = filled(version);
+        group.bench_function(BenchmarkId::from_parameter(version), |b| {
+            b.iter_batched(
+                || qrcode.clone(),
+                |mut qrcode| {
+                    qrcode.apply_mask();
+                    qrcode
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("mask_selection");
+    for version in VERSIONS {
+        let qrcode = filled(version);
+        group.bench_function(BenchmarkId::from_parameter(version), |b| {
+            b.iter_batched(
+                || qrcode.clone(),
+                |mut qrcode| {
+                    qrcode.apply_best_mask();
+                    qrcode
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    for version in VERSIONS {
+        let mut qrcode = filled(version);
+        qrcode.apply_mask();
+        group.bench_function(BenchmarkId::new("display", version), |b| {
+            b.iter(|| qrcode.to_string())
+        });
+        group.bench_function(BenchmarkId::new("svg", version), |b| {
+            b.iter(|| qrcode.to_svg(&SvgOptions::default()))
+        });
+        group.bench_function(BenchmarkId::new("png", version), |b| {
+            b.iter(|| qrcode.to_png(&PngOptions::default()))
+        });
+    }
+    group.finish();
+}
+
+fn generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+    let encoder = Encoder::new();
+    for version in VERSIONS {
+        let data = payload(version, b"https://example.com/");
+        group.bench_with_input(
+            BenchmarkId::new("preprocessor", version),
+            &data,
+            |b, data| {
+                b.iter(|| {
+                    Preprocessor::new(data, Encoding::Byte, EC_LEVEL, MaskPattern::Checkerboard)
+                        .generate_qrcode()
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("encoder", version), &data, |b, data| {
+            b.iter(|| encoder.encode(data, Encoding::Byte, EC_LEVEL, MaskPattern::Checkerboard))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    encoding,
+    error_correction,
+    functional_patterns,
+    fill,
+    mask,
+    render,
+    generate
+);
+criterion_main!(benches);
@@ -1,7 +1,36 @@
+/// What a module of the symbol is part of.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ModuleRole {
+    /// Not drawn or filled yet, also used for bits that are not placed in a symbol.
+    Empty,
+    Finder,
+    Separator,
+    Timing,
+    Alignment,
+    FormatInfo,
+    VersionInfo,
+    DarkModule,
+    /// Data codeword, with the index of the codeword in the interleaved sequence.
+    Data(u16),
+    /// Error correction codeword, with the index of the codeword in the interleaved sequence.
+    Ec(u16),
+    /// Remainder bit following the last codeword.
+    Remainder,
+}
+
+impl ModuleRole {
+    pub fn is_functional(&self) -> bool {
+        !matches!(
+            self,
+            ModuleRole::Empty | ModuleRole::Data(_) | ModuleRole::Ec(_) | ModuleRole::Remainder
+        )
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Bit {
-    One(bool),
-    Zero(bool),
+    One(ModuleRole),
+    Zero(ModuleRole),
 }
 
 impl PartialEq for Bit {
@@ -15,13 +44,29 @@ impl PartialEq for Bit {
 }
 
 impl Bit {
-    pub fn is_functional(&self) -> bool {
+    pub fn new(value: bool, role: ModuleRole) -> Bit {
+        if value {
+            Bit::One(role)
+        } else {
+            Bit::Zero(role)
+        }
+    }
+
+    pub fn role(&self) -> ModuleRole {
         match self {
-            Bit::One(val) => *val,
-            Bit::Zero(val) => *val,
+            Bit::One(role) => *role,
+            Bit::Zero(role) => *role,
         }
     }
 
+    pub fn with_role(&self, role: ModuleRole) -> Bit {
+        Bit::new(self.value(), role)
+    }
+
+    pub fn is_functional(&self) -> bool {
+        self.role().is_functional()
+    }
+
     pub fn value(&self) -> bool {
         match self {
             Bit::One(_) => true,
@@ -29,27 +74,25 @@ impl Bit {
         }
     }
 
-    pub fn from(data: u32, n_bits: u8, functional: bool, reverse: bool) -> Vec<Bit> {
-        let mut bits = Vec::new();
-        for i in 0..n_bits {
-            let mask = if reverse {
-                1 << (n_bits - i - 1)
-            } else {
-                1 << i
-            };
-            let bit = (data & mask) != 0;
-            match bit {
-                true => {
-                    bits.push(Bit::One(functional));
-                }
-                false => {
-                    bits.push(Bit::Zero(functional));
-                }
-            }
+    pub fn from(data: u32, n_bits: u8, role: ModuleRole, reverse: bool) -> Vec<Bit> {
+        let mut bits = Vec::with_capacity(n_bits as usize);
+        if reverse {
+            Self::push_bits(&mut bits, data, n_bits, role);
+        } else {
+            bits.extend((0..n_bits).map(|i| Bit::new(data & (1 << i) != 0, role)));
         }
         bits
     }
 
+    /// Appends the `n_bits` lowest bits of `data` to `bits`, most significant bit first.
+    pub fn push_bits(bits: &mut Vec<Bit>, data: u32, n_bits: u8, role: ModuleRole) {
+        bits.extend(
+            (0..n_bits)
+                .rev()
+                .map(|i| Bit::new(data & (1 << i) != 0, role)),
+        );
+    }
+
     pub fn invert(&self) -> Bit {
         match self {
             Bit::One(val) => Bit::Zero(*val),
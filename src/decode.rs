@@ -0,0 +1,373 @@
+use crate::ec::{correct, data_codewords_count, deinterleave, ec_codewords_per_block, EcLevel};
+use crate::encoding::Encoding;
+use crate::mask::MaskPattern;
+use crate::preprocessor::Preprocessor;
+use crate::qrcode::QrCode;
+use crate::validate::{bch_remainder, FORMAT_GENERATOR, FORMAT_MASK};
+
+const ALPHANUMERIC_CHARS: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Content and parameters read back from a symbol.
+#[derive(Debug)]
+pub struct Decoded {
+    /// Decoded segments, Kanji as Shift JIS.
+    pub data: Vec<u8>,
+    pub version: u8,
+    pub ec_level: EcLevel,
+    pub mask_pattern: MaskPattern,
+    /// Number of codewords repaired by error correction.
+    pub corrected: usize,
+}
+
+/// Decodes a symbol from its modules, row by row, `true` for dark.
+pub fn decode_modules(size: u32, modules: &[bool]) -> Result<Decoded, String> {
+    if !(21..=177).contains(&size) || !(size - 17).is_multiple_of(4) {
+        return Err(format!("{} modules is not a valid symbol size.", size));
+    }
+    if modules.len() != (size * size) as usize {
+        return Err("Module count does not match the symbol size.".to_string());
+    }
+    let version = ((size - 17) / 4) as u8;
+    let dark = |x: u32, y: u32| modules[(y * size + x) as usize];
+
+    let (ec_level, mask_pattern) = read_format(size, &dark)?;
+
+    let mut template = QrCode::new(version, ec_level, mask_pattern, Encoding::Byte)?;
+    template.all_functional_patterns();
+    let mask_fn = mask_pattern.get_mask();
+    let bits: Vec<bool> = template
+        .data_module_indices()
+        .into_iter()
+        .map(|index| {
+            let (x, y) = (index % size, index / size);
+            dark(x, y) != mask_fn(x, y)
+        })
+        .collect();
+    let codewords: Vec<u8> = bits
+        .chunks_exact(8)
+        .map(|byte| byte.iter().fold(0, |acc, &bit| (acc << 1) | bit as u8))
+        .collect();
+
+    let ec_size = ec_codewords_per_block(version, &ec_level);
+    let mut data = Vec::with_capacity(data_codewords_count(version, &ec_level));
+    let mut corrected = 0;
+    for (i, mut block) in deinterleave(&codewords, version, &ec_level)
+        .into_iter()
+        .enumerate()
+    {
+        corrected += correct(&mut block, ec_size).map_err(|err| format!("Block {}: {}", i, err))?;
+        data.extend_from_slice(&block[..block.len() - ec_size]);
+    }
+
+    Ok(Decoded {
+        data: read_segments(version, &data)?,
+        version,
+        ec_level,
+        mask_pattern,
+        corrected,
+    })
+}
+
+/// Reads the EC level and mask from whichever format information copy is closest to a valid
+/// codeword, up to 3 bit errors.
+fn read_format(
+    size: u32,
+    dark: &dyn Fn(u32, u32) -> bool,
+) -> Result<(EcLevel, MaskPattern), String> {
+    let first = (0..9)
+        .filter(|&x| x != 6)
+        .map(|x| (x, 8))
+        .chain((0..8).rev().filter(|&y| y != 6).map(|y| (8, y)));
+    let second = ((size - 7)..size)
+        .rev()
+        .map(|y| (8, y))
+        .chain(((size - 8)..size).map(|x| (x, 8)));
+    let first: u32 = first.fold(0, |acc, (x, y)| (acc << 1) | dark(x, y) as u32);
+    let second: u32 = second.fold(0, |acc, (x, y)| (acc << 1) | dark(x, y) as u32);
+
+    let (distance, format) = (0..32u32)
+        .flat_map(|format| {
+            let codeword =
+                ((format << 10) | bch_remainder(format << 10, FORMAT_GENERATOR)) ^ FORMAT_MASK;
+            [first, second].map(|read| ((read ^ codeword).count_ones(), format))
+        })
+        .min()
+        .unwrap();
+    if distance > 3 {
+        return Err("Format information is unreadable.".to_string());
+    }
+
+    let ec_level = match format >> 3 {
+        0b01 => EcLevel::L,
+        0b00 => EcLevel::M,
+        0b11 => EcLevel::Q,
+        _ => EcLevel::H,
+    };
+    Ok((ec_level, MaskPattern::ALL[(format & 0b111) as usize]))
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    fn read(&mut self, n_bits: u8) -> Result<u32, String> {
+        if self.remaining() < n_bits as usize {
+            return Err("Segment is truncated.".to_string());
+        }
+        let mut value = 0;
+        for _ in 0..n_bits {
+            let bit = self.data[self.position / 8] >> (7 - self.position % 8) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+fn read_segments(version: u8, data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = BitReader { data, position: 0 };
+    let mut content = Vec::new();
+
+    // a missing or partial terminator is allowed when the data codewords are full
+    while reader.remaining() >= 4 {
+        let encoding = match reader.read(4)? {
+            0b0000 => break,
+            0b0001 => Encoding::Numeric,
+            0b0010 => Encoding::Alphanumeric,
+            0b0100 => Encoding::Byte,
+            0b1000 => Encoding::Kanji,
+            mode => return Err(format!("Mode {:04b} is not supported.", mode)),
+        };
+        let count = reader.read(Preprocessor::char_count(version, encoding))? as usize;
+
+        match encoding {
+            Encoding::Numeric => {
+                for group in (0..count).step_by(3) {
+                    let digits = (count - group).min(3);
+                    let value = reader.read([4, 7, 10][digits - 1])?;
+                    let text = format!("{:0width$}", value, width = digits);
+                    if text.len() != digits {
+                        return Err("Numeric group is out of range.".to_string());
+                    }
+                    content.extend_from_slice(text.as_bytes());
+                }
+            }
+            Encoding::Alphanumeric => {
+                for pair in (0..count).step_by(2) {
+                    if count - pair >= 2 {
+                        let value = reader.read(11)? as usize;
+                        let (first, second) = (value / 45, value % 45);
+                        if first >= 45 {
+                            return Err("Alphanumeric pair is out of range.".to_string());
+                        }
+                        content.push(ALPHANUMERIC_CHARS[first]);
+                        content.push(ALPHANUMERIC_CHARS[second]);
+                    } else {
+                        let value = reader.read(6)? as usize;
+                        let char = ALPHANUMERIC_CHARS
+                            .get(value)
+                            .ok_or("Alphanumeric character is out of range.")?;
+                        content.push(*char);
+                    }
+                }
+            }
+            Encoding::Byte => {
+                for _ in 0..count {
+                    content.push(reader.read(8)? as u8);
+                }
+            }
+            Encoding::Kanji => {
+                for _ in 0..count {
+                    let value = reader.read(13)?;
+                    let packed = ((value / 0xC0) << 8) | (value % 0xC0);
+                    let sjis = if packed < 0x1F00 {
+                        packed + 0x8140
+                    } else {
+                        packed + 0xC140
+                    };
+                    content.extend_from_slice(&(sjis as u16).to_be_bytes());
+                }
+            }
+        }
+    }
+
+    Ok(content)
+}
+
+/// Decodes an upright symbol from an 8-bit grayscale image, like the ones rendered by this crate.
+/// The symbol is the bounding box of the dark pixels, so the quiet zone has to be clean.
+pub fn decode_luma(width: u32, height: u32, pixels: &[u8]) -> Result<Decoded, String> {
+    if (width as usize).checked_mul(height as usize) != Some(pixels.len()) {
+        return Err("Pixel count does not match the image size.".to_string());
+    }
+    if width == 0 || height == 0 {
+        return Err("No QR code found.".to_string());
+    }
+    let (min, max) = pixels
+        .iter()
+        .fold((255, 0), |(min, max), &luma| (luma.min(min), luma.max(max)));
+    let threshold = (min as u32 + max as u32).div_ceil(2) as u8;
+    let dark = |x: u32, y: u32| pixels[y as usize * width as usize + x as usize] < threshold;
+    if min == max {
+        return Err("No QR code found.".to_string());
+    }
+
+    let rows: Vec<u32> = (0..height)
+        .filter(|&y| (0..width).any(|x| dark(x, y)))
+        .collect();
+    let columns: Vec<u32> = (0..width)
+        .filter(|&x| (0..height).any(|y| dark(x, y)))
+        .collect();
+    let (top, bottom, left, right) =
+        match (rows.first(), rows.last(), columns.first(), columns.last()) {
+            (Some(&top), Some(&bottom), Some(&left), Some(&right)) => {
+                (top, bottom + 1, left, right + 1)
+            }
+            _ => return Err("No QR code found.".to_string()),
+        };
+    let extent = (right - left) as f64;
+    if ((bottom - top) as f64 - extent).abs() > extent / 21.0 {
+        return Err("No QR code found.".to_string());
+    }
+
+    // try every version whose modules are at least a pixel wide
+    let mut error = "No QR code found.".to_string();
+    for version in 1..=40u32 {
+        let size = 17 + 4 * version;
+        let module = extent / size as f64;
+        if module < 1.0 {
+            break;
+        }
+
+        let sample = |i: u32, start: u32| start + ((i as f64 + 0.5) * module) as u32;
+        let modules: Vec<bool> = (0..size)
+            .flat_map(|y| (0..size).map(move |x| (x, y)))
+            .map(|(x, y)| dark(sample(x, left), sample(y, top)))
+            .collect();
+        match decode_modules(size, &modules) {
+            Ok(decoded) => return Ok(decoded),
+            Err(err) => error = err,
+        }
+    }
+
+    Err(error)
+}
+
+/// Rec. 601 luma of a colour.
+pub(crate) fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+}
+
+/// Decodes an upright symbol from 8-bit RGBA pixels, composited over white.
+pub fn decode_rgba(width: u32, height: u32, pixels: &[u8]) -> Result<Decoded, String> {
+    let composited: Vec<u8> = pixels
+        .chunks_exact(4)
+        .map(|pixel| {
+            let luma = luma(pixel[0], pixel[1], pixel[2]) as u32;
+            let alpha = pixel[3] as u32;
+            ((luma * alpha + 255 * (255 - alpha)) / 255) as u8
+        })
+        .collect();
+    decode_luma(width, height, &composited)
+}
+
+impl QrCode {
+    /// Reads the symbol back from its modules.
+    pub fn decode(&self) -> Result<Decoded, String> {
+        let modules: Vec<bool> = self.data.iter().map(|bit| bit.value()).collect();
+        decode_modules(self.size(), &modules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::Preprocessor;
+
+    fn qrcode(data: &str, encoding: Encoding, ec_level: EcLevel) -> QrCode {
+        Preprocessor::new(data, encoding, ec_level, MaskPattern::Diamonds).generate_qrcode()
+    }
+
+    #[test]
+    fn decode_reads_every_mode() {
+        for (data, encoding) in [
+            ("0123456789", Encoding::Numeric),
+            ("HELLO WORLD $%*+-./:", Encoding::Alphanumeric),
+            ("https://example.com/?q=1", Encoding::Byte),
+        ] {
+            let decoded = qrcode(data, encoding, EcLevel::Q).decode().unwrap();
+            assert_eq!(decoded.data, data.as_bytes());
+            assert_eq!(decoded.corrected, 0);
+            assert_eq!(decoded.ec_level, EcLevel::Q);
+            assert_eq!(decoded.mask_pattern, MaskPattern::Diamonds);
+        }
+    }
+
+    #[test]
+    fn decode_corrects_damaged_modules() {
+        let data = "a long enough text to need a version with several blocks of codewords";
+        let mut qrcode = qrcode(data, Encoding::Byte, EcLevel::H);
+        assert!(qrcode.version() >= 7);
+
+        // blot out a square in the middle and flip a format bit
+        let middle = qrcode.size() / 2;
+        for y in middle - 3..middle + 3 {
+            for x in middle - 3..middle + 3 {
+                let index = (y * qrcode.size() + x) as usize;
+                qrcode.data[index] = qrcode.data[index].invert();
+            }
+        }
+        let format_bit = 2 * qrcode.size() as usize + 8;
+        qrcode.data[format_bit] = qrcode.data[format_bit].invert();
+
+        let decoded = qrcode.decode().unwrap();
+        assert_eq!(decoded.data, data.as_bytes());
+        assert!(decoded.corrected > 0);
+    }
+
+    #[test]
+    fn decode_luma_finds_scaled_symbol() {
+        let qrcode = qrcode("HELLO", Encoding::Alphanumeric, EcLevel::M);
+        let (scale, quiet_zone) = (3, 2);
+        let width = (qrcode.size() + 2 * quiet_zone) * scale;
+        let pixels: Vec<u8> = (0..width * width)
+            .map(|i| {
+                let (x, y) = (i % width / scale, i / width / scale);
+                let dark = crate::render::dark_at(&qrcode, quiet_zone, x, y);
+                if dark {
+                    20
+                } else {
+                    230
+                }
+            })
+            .collect();
+
+        let decoded = decode_luma(width, width, &pixels).unwrap();
+        assert_eq!(decoded.data, b"HELLO");
+        assert!(decode_luma(width, width, &vec![255; (width * width) as usize]).is_err());
+    }
+
+    #[test]
+    fn decode_luma_rejects_empty_images() {
+        let not_found = Err("No QR code found.".to_string());
+        assert_eq!(decode_luma(0, 0, &[]).map(|d| d.data), not_found);
+        assert_eq!(decode_luma(0, 5, &[]).map(|d| d.data), not_found);
+        assert_eq!(decode_luma(5, 0, &[]).map(|d| d.data), not_found);
+    }
+
+    #[test]
+    fn decode_luma_rejects_overflowing_sizes() {
+        let mismatch = Err("Pixel count does not match the image size.".to_string());
+        assert_eq!(decode_luma(70000, 70000, &[]).map(|d| d.data), mismatch);
+        assert_eq!(
+            decode_luma(u32::MAX, u32::MAX, &[0]).map(|d| d.data),
+            mismatch
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use crate::tables::{DATA_BYTES_PER_BLOCK, EXP_TABLE, GENERATOR_POLYNOMIALS, LOG_TABLE};
+use crate::tables::{
+    DATA_BYTES_PER_BLOCK, EC_BYTES_PER_BLOCK, EXP_TABLE, GENERATOR_POLYNOMIALS, LOG_TABLE,
+    SIZE_EC_H, SIZE_EC_L, SIZE_EC_M, SIZE_EC_Q,
+};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EcLevel {
     H,
     Q,
@@ -19,8 +22,34 @@ impl EcLevel {
     }
 }
 
+/// Number of data codewords (excluding error correction) for a version and EC level.
+pub fn data_codewords_count(version: u8, ec_level: &EcLevel) -> usize {
+    let table = match ec_level {
+        EcLevel::L => &SIZE_EC_L,
+        EcLevel::M => &SIZE_EC_M,
+        EcLevel::Q => &SIZE_EC_Q,
+        EcLevel::H => &SIZE_EC_H,
+    };
+    table[(version - 1) as usize] as usize
+}
+
+/// Number of error correction codewords in each block for a version and EC level.
+pub fn ec_codewords_per_block(version: u8, ec_level: &EcLevel) -> usize {
+    EC_BYTES_PER_BLOCK[(version - 1) as usize][ec_level.ordinal() as usize]
+}
+
+/// Number of data codewords in each block, in block order.
+pub fn block_sizes(version: u8, ec_level: &EcLevel) -> Vec<usize> {
+    let (block_1_size, block_1_count, block_2_size, block_2_count) =
+        DATA_BYTES_PER_BLOCK[(version - 1) as usize][ec_level.ordinal() as usize];
+
+    let mut sizes = vec![block_1_size; block_1_count];
+    sizes.resize(block_1_count + block_2_count, block_2_size);
+    sizes
+}
+
 pub fn codewords(
-    data: &Vec<u8>,
+    data: &[u8],
     version: u8,
     ec_level: &EcLevel,
     cw_per_block: usize,
@@ -31,7 +60,7 @@ pub fn codewords(
         .iter()
         .map(|block| {
             create_ec_for_block(
-                Vec::from(block.clone()),
+                block.clone(),
                 cw_per_block,
                 GENERATOR_POLYNOMIALS[cw_per_block],
             )
@@ -41,7 +70,7 @@ pub fn codewords(
     (interleave(blocks), interleave(ec_blocks))
 }
 
-pub fn groups(data: &Vec<u8>, version: u8, ec_level: &EcLevel) -> Vec<Vec<u8>> {
+pub fn groups(data: &[u8], version: u8, ec_level: &EcLevel) -> Vec<Vec<u8>> {
     let ec_level = ec_level.ordinal();
     let (block_1_size, block_1_count, block_2_size, block_2_count) =
         DATA_BYTES_PER_BLOCK[(version - 1) as usize][ec_level as usize];
@@ -93,6 +122,168 @@ fn create_ec_for_block(block: Vec<u8>, ec_size: usize, generator_polynomial: &[u
     codewords.split_off(data_len)
 }
 
+/// Splits interleaved data and EC codewords back into blocks, each block being its data
+/// codewords followed by its EC codewords.
+pub fn deinterleave(codewords: &[u8], version: u8, ec_level: &EcLevel) -> Vec<Vec<u8>> {
+    let data_sizes = block_sizes(version, ec_level);
+    let ec_size = ec_codewords_per_block(version, ec_level);
+    let max_data_size = data_sizes.iter().copied().max().unwrap_or(0);
+
+    let mut blocks: Vec<Vec<u8>> = data_sizes
+        .iter()
+        .map(|size| Vec::with_capacity(size + ec_size))
+        .collect();
+    let mut codewords = codewords.iter().copied();
+
+    for i in 0..max_data_size {
+        for (block, &size) in blocks.iter_mut().zip(&data_sizes) {
+            if i < size {
+                block.extend(codewords.next());
+            }
+        }
+    }
+    for _ in 0..ec_size {
+        for block in blocks.iter_mut() {
+            block.extend(codewords.next());
+        }
+    }
+
+    blocks
+}
+
+/// Syndromes of a block made of data then EC codewords, all of them are 0 when the block has
+/// no errors.
+pub fn syndromes(block: &[u8], ec_size: usize) -> Vec<u8> {
+    (0..ec_size)
+        .map(|i| {
+            // evaluate the block polynomial at 2^i
+            block.iter().fold(0u8, |acc, &codeword| {
+                let product = if acc == 0 {
+                    0
+                } else {
+                    EXP_TABLE[(usize::from(LOG_TABLE[acc as usize]) + i) % 255]
+                };
+                product ^ codeword
+            })
+        })
+        .collect()
+}
+
+fn multiply(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        EXP_TABLE[(usize::from(LOG_TABLE[a as usize]) + usize::from(LOG_TABLE[b as usize])) % 255]
+    }
+}
+
+fn divide(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        0
+    } else {
+        EXP_TABLE
+            [(usize::from(LOG_TABLE[a as usize]) + 255 - usize::from(LOG_TABLE[b as usize])) % 255]
+    }
+}
+
+/// Evaluates a polynomial with coefficients from the lowest degree at `x`.
+fn evaluate(polynomial: &[u8], x: u8) -> u8 {
+    polynomial
+        .iter()
+        .rev()
+        .fold(0, |acc, &coefficient| multiply(acc, x) ^ coefficient)
+}
+
+/// Corrects a block made of data then EC codewords in place, returns the number of corrected
+/// codewords. Fails when the block has more errors than its EC codewords can correct.
+pub fn correct(block: &mut [u8], ec_size: usize) -> Result<usize, String> {
+    let syndromes = syndromes(block, ec_size);
+    if syndromes.iter().all(|&s| s == 0) {
+        return Ok(0);
+    }
+
+    // Berlekamp-Massey, polynomials have their lowest degree first
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut errors = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = 1u8;
+    for n in 0..ec_size {
+        let discrepancy = (1..=errors).fold(syndromes[n], |acc, i| {
+            acc ^ multiply(locator.get(i).copied().unwrap_or(0), syndromes[n - i])
+        });
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        let factor = divide(discrepancy, previous_discrepancy);
+        let snapshot = locator.clone();
+        locator.resize(locator.len().max(previous.len() + shift), 0);
+        for (i, &coefficient) in previous.iter().enumerate() {
+            locator[i + shift] ^= multiply(factor, coefficient);
+        }
+        if 2 * errors <= n {
+            errors = n + 1 - errors;
+            previous = snapshot;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+    if 2 * errors > ec_size {
+        return Err("Too many errors to correct.".to_string());
+    }
+
+    // the codeword at index j is the coefficient of x^(n - 1 - j)
+    let n = block.len();
+    let positions: Vec<usize> = (0..n)
+        .filter(|&j| {
+            let inverse = EXP_TABLE[(255 - (n - 1 - j) % 255) % 255];
+            evaluate(&locator, inverse) == 0
+        })
+        .collect();
+    if positions.len() != errors {
+        return Err("Too many errors to correct.".to_string());
+    }
+
+    // Forney, with generator roots starting at 2^0
+    let mut evaluator: Vec<u8> = vec![0; ec_size];
+    for (i, &syndrome) in syndromes.iter().enumerate() {
+        for (k, &coefficient) in locator.iter().enumerate() {
+            if i + k < ec_size {
+                evaluator[i + k] ^= multiply(syndrome, coefficient);
+            }
+        }
+    }
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &coefficient)| if i % 2 == 1 { coefficient } else { 0 })
+        .collect();
+    for &j in &positions {
+        let x = EXP_TABLE[(n - 1 - j) % 255];
+        let inverse = divide(1, x);
+        let magnitude = divide(
+            multiply(x, evaluate(&evaluator, inverse)),
+            evaluate(&derivative, inverse),
+        );
+        block[j] ^= magnitude;
+    }
+
+    if syndromes_are_zero(block, ec_size) {
+        Ok(errors)
+    } else {
+        Err("Too many errors to correct.".to_string())
+    }
+}
+
+fn syndromes_are_zero(block: &[u8], ec_size: usize) -> bool {
+    syndromes(block, ec_size).iter().all(|&s| s == 0)
+}
+
 pub fn interleave(blocks: Vec<Vec<u8>>) -> Vec<u8> {
     let mut result = Vec::new();
     let max_len = blocks.iter().map(|block| block.len()).max().unwrap();
@@ -164,4 +355,50 @@ mod ec_tests {
             168, 72, 22, 82, 217, 54, 156, 0, 46, 15, 180, 122, 16
         ]));
     }
+
+    #[test]
+    fn syndromes_are_zero_for_valid_block() {
+        let mut block = vec![32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236];
+        block.extend([168, 72, 22, 82, 217, 54, 156, 0, 46, 15, 180, 122, 16]);
+        assert!(syndromes(&block, 13).iter().all(|&s| s == 0));
+
+        block[3] ^= 0x40;
+        assert!(syndromes(&block, 13).iter().any(|&s| s != 0));
+    }
+
+    #[test]
+    fn correct_repairs_up_to_half_the_ec_codewords() {
+        let mut valid = vec![32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236];
+        valid.extend([168, 72, 22, 82, 217, 54, 156, 0, 46, 15, 180, 122, 16]);
+
+        let mut block = valid.clone();
+        assert_eq!(correct(&mut block, 13), Ok(0));
+
+        for (i, position) in [0, 5, 12, 13, 17, 25].iter().enumerate() {
+            block[*position] ^= 0x11 * (i as u8 + 1);
+        }
+        assert_eq!(correct(&mut block, 13), Ok(6));
+        assert_eq!(block, valid);
+
+        for position in [1, 3, 5, 7, 9, 11, 20] {
+            block[position] ^= 0xFF;
+        }
+        assert!(correct(&mut block, 13).is_err());
+    }
+
+    #[test]
+    fn deinterleave_reverses_codewords() {
+        let data: Vec<u8> = (0..62).collect();
+        let (data_codewords, ec_codewords) = codewords(&data, 5, &EcLevel::Q, 18);
+        let mut interleaved = data_codewords;
+        interleaved.extend(ec_codewords);
+
+        let blocks = deinterleave(&interleaved, 5, &EcLevel::Q);
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(&blocks[0][..15], &data[..15]);
+        assert_eq!(&blocks[3][..16], &data[46..]);
+        for block in blocks {
+            assert!(syndromes(&block, 18).iter().all(|&s| s == 0));
+        }
+    }
 }
@@ -0,0 +1,163 @@
+use crate::bit::Bit;
+use crate::ec::EcLevel;
+use crate::encoding::Encoding;
+use crate::mask::MaskPattern;
+use crate::preprocessor::Preprocessor;
+use crate::qrcode::QrCode;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+/// Functional patterns of a version, drawn once and copied into every symbol of that version.
+struct Template {
+    qrcode: QrCode,
+    data_modules: Vec<u32>,
+}
+
+#[derive(Default)]
+struct Scratch {
+    segment: Vec<Bit>,
+    bits: Vec<Bit>,
+}
+
+/// Generates many QR codes without redrawing the functional patterns for each of them.
+///
+/// Templates are built on first use of a version and shared afterwards, so a single `Encoder`
+/// can be used from several threads at once.
+pub struct Encoder {
+    templates: [OnceLock<Template>; 40],
+    scratch: Mutex<Vec<Scratch>>,
+}
+
+impl Encoder {
+    pub fn new() -> Encoder {
+        Encoder {
+            templates: std::array::from_fn(|_| OnceLock::new()),
+            scratch: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Same as `Preprocessor::new(..).generate_qrcode()`, but returns an error instead of
+    /// panicking when `data` can't be encoded.
+    pub fn encode(
+        &self,
+        data: &str,
+        encoding: Encoding,
+        ec_level: EcLevel,
+        mask_pattern: MaskPattern,
+    ) -> Result<QrCode, String> {
+        let mut scratch = self
+            .scratch
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()
+            .unwrap_or_default();
+
+        let result = Preprocessor::encode_into(
+            data,
+            encoding,
+            ec_level,
+            &mut scratch.segment,
+            &mut scratch.bits,
+        )
+        .map(|version| {
+            let template = self.template(version);
+            let mut qrcode = template.qrcode.clone();
+            qrcode.reconfigure(ec_level, mask_pattern, encoding);
+            qrcode.fill_modules(&template.data_modules, &scratch.bits);
+            qrcode.apply_mask_modules(&template.data_modules);
+            qrcode
+        });
+
+        self.scratch
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(scratch);
+
+        result
+    }
+
+    fn template(&self, version: u8) -> &Template {
+        self.templates[(version - 1) as usize].get_or_init(|| {
+            let mut qrcode = QrCode::new(
+                version,
+                EcLevel::L,
+                MaskPattern::Checkerboard,
+                Encoding::Byte,
+            )
+            .expect("valid version");
+            qrcode.all_functional_patterns();
+            let data_modules = qrcode.data_module_indices();
+            Template {
+                qrcode,
+                data_modules,
+            }
+        })
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoder_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Encoder>();
+    }
+
+    #[test]
+    fn encode_matches_preprocessor() {
+        let encoder = Encoder::new();
+        let inputs = [
+            (
+                "01234567",
+                Encoding::Numeric,
+                EcLevel::M,
+                MaskPattern::Horizontal,
+            ),
+            (
+                "HELLO WORLD",
+                Encoding::Alphanumeric,
+                EcLevel::Q,
+                MaskPattern::Fields,
+            ),
+            (
+                "https://example.com",
+                Encoding::Byte,
+                EcLevel::L,
+                MaskPattern::Diagonal,
+            ),
+            (
+                &"https://example.com/".repeat(20),
+                Encoding::Byte,
+                EcLevel::H,
+                MaskPattern::Meadow,
+            ),
+        ];
+
+        // twice, so the second round reuses the templates and scratch buffers
+        for _ in 0..2 {
+            for (data, encoding, ec_level, mask_pattern) in inputs {
+                let expected =
+                    Preprocessor::new(data, encoding, ec_level, mask_pattern).generate_qrcode();
+                let qrcode = encoder
+                    .encode(data, encoding, ec_level, mask_pattern)
+                    .unwrap();
+                assert_eq!(qrcode.version(), expected.version());
+                assert_eq!(qrcode.data, expected.data);
+            }
+        }
+    }
+
+    #[test]
+    fn encode_returns_error_for_invalid_data() {
+        let encoder = Encoder::new();
+        let result = encoder.encode("12a", Encoding::Numeric, EcLevel::L, MaskPattern::Fields);
+        assert_eq!(result.err(), Some("Invalid character: a".to_string()));
+    }
+}
@@ -1,41 +1,19 @@
-use crate::bit::Bit;
+use crate::bit::{Bit, ModuleRole};
 
 pub fn to_bits_str(data: &str) -> Vec<Bit> {
-    data.chars()
-        .map(|c| {
-            let mut bits = vec![];
-            for i in 0..8 {
-                let bit = (c as u8 >> i) & 1;
-                bits.push(if bit == 1 {
-                    Bit::One(false)
-                } else {
-                    Bit::Zero(false)
-                });
-            }
-            bits.reverse();
-            bits
-        })
-        .flatten()
-        .collect()
+    let mut bits = Vec::with_capacity(data.len() * 8);
+    for c in data.chars() {
+        Bit::push_bits(&mut bits, c as u8 as u32, 8, ModuleRole::Empty);
+    }
+    bits
 }
 
 pub fn to_bits_array(data: &[u8]) -> Vec<Bit> {
-    data.iter()
-        .map(|c| {
-            let mut bits = vec![];
-            for i in 0..8 {
-                let bit = (c >> i) & 1u8;
-                bits.push(if bit == 1u8 {
-                    Bit::One(false)
-                } else {
-                    Bit::Zero(false)
-                });
-            }
-            bits.reverse();
-            bits
-        })
-        .flatten()
-        .collect()
+    let mut bits = Vec::with_capacity(data.len() * 8);
+    for &byte in data {
+        Bit::push_bits(&mut bits, byte as u32, 8, ModuleRole::Empty);
+    }
+    bits
 }
 
 #[derive(Copy, Clone)]
@@ -51,71 +29,71 @@ impl Encoding {
         match self {
             Encoding::Numeric => {
                 vec![
-                    Bit::Zero(false),
-                    Bit::Zero(false),
-                    Bit::Zero(false),
-                    Bit::One(false),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::One(ModuleRole::Empty),
                 ]
             }
             Encoding::Alphanumeric => {
                 vec![
-                    Bit::Zero(false),
-                    Bit::Zero(false),
-                    Bit::One(false),
-                    Bit::Zero(false),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::One(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
                 ]
             }
             Encoding::Byte => {
                 vec![
-                    Bit::Zero(false),
-                    Bit::One(false),
-                    Bit::Zero(false),
-                    Bit::Zero(false),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::One(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
                 ]
             }
             Encoding::Kanji => {
                 vec![
-                    Bit::One(false),
-                    Bit::Zero(false),
-                    Bit::Zero(false),
-                    Bit::Zero(false),
+                    Bit::One(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
+                    Bit::Zero(ModuleRole::Empty),
                 ]
             }
         }
     }
 
     pub fn encode(&self, data: &str) -> Result<Vec<Bit>, String> {
+        let mut bits = Vec::new();
+        self.encode_into(data, &mut bits)?;
+        Ok(bits)
+    }
+
+    /// Same as `encode`, but appends the bits to an existing buffer.
+    pub fn encode_into(&self, data: &str, bits: &mut Vec<Bit>) -> Result<(), String> {
         match self {
-            Encoding::Numeric => Encoding::encode_numeric(data),
-            Encoding::Alphanumeric => Encoding::encode_alphanumeric(data),
-            Encoding::Byte => Encoding::encode_byte(data),
+            Encoding::Numeric => Encoding::encode_numeric(data, bits),
+            Encoding::Alphanumeric => Encoding::encode_alphanumeric(data, bits),
+            Encoding::Byte => Encoding::encode_byte(data, bits),
             Encoding::Kanji => {
                 unimplemented!()
             }
         }
     }
 
-    fn encode_alphanumeric(data: &str) -> Result<Vec<Bit>, String> {
-        let pairs = data
-            .chars()
-            .map(|c| Self::alphanumeric_value(c))
-            .collect::<Result<Vec<u16>, String>>();
-
-        match pairs {
-            Ok(vec) => Ok(vec
-                .chunks(2)
-                .flat_map(|pair| {
-                    if pair.len() == 2 {
-                        let value = pair[0] * 45 + pair[1];
-                        Bit::from(value as u32, 11, false, true)
-                    } else {
-                        let value = pair[0];
-                        Bit::from(value as u32, 6, false, true)
-                    }
-                })
-                .collect()),
-            Err(msg) => Err(msg),
+    fn encode_alphanumeric(data: &str, bits: &mut Vec<Bit>) -> Result<(), String> {
+        bits.reserve(data.len() / 2 * 11 + 6);
+
+        let mut chars = data.chars();
+        while let Some(first) = chars.next() {
+            let first = Self::alphanumeric_value(first)?;
+            if let Some(second) = chars.next() {
+                let value = first * 45 + Self::alphanumeric_value(second)?;
+                Bit::push_bits(bits, value as u32, 11, ModuleRole::Empty);
+            } else {
+                Bit::push_bits(bits, first as u32, 6, ModuleRole::Empty);
+            }
         }
+        Ok(())
     }
 
     fn alphanumeric_value(c: char) -> Result<u16, String> {
@@ -135,17 +113,13 @@ impl Encoding {
         }
     }
 
-    fn encode_byte(data: &str) -> Result<Vec<Bit>, String> {
-        let bytes: Result<Vec<u8>, String> =
-            data.chars().map(|c| Self::char_to_iso_8859_1(c)).collect();
-
-        match bytes {
-            Ok(vec) => Ok(vec
-                .iter()
-                .flat_map(|byte| Bit::from(*byte as u32, 8, false, true))
-                .collect()),
-            Err(msg) => Err(msg),
+    fn encode_byte(data: &str, bits: &mut Vec<Bit>) -> Result<(), String> {
+        bits.reserve(data.len() * 8);
+        for c in data.chars() {
+            let byte = Self::char_to_iso_8859_1(c)?;
+            Bit::push_bits(bits, byte as u32, 8, ModuleRole::Empty);
         }
+        Ok(())
     }
 
     fn char_to_iso_8859_1(c: char) -> Result<u8, String> {
@@ -156,25 +130,56 @@ impl Encoding {
         }
     }
 
-    fn encode_numeric(data: &str) -> Result<Vec<Bit>, String> {
-        let mut bits = vec![];
-        let mut i = 0;
-        while i < data.len() {
-            let mut value = 0;
-            for j in 0..3 {
-                if i + j < data.len() {
-                    let digit = data.chars().nth(i + j).unwrap();
-                    if digit < '0' || digit > '9' {
-                        return Err(format!("Invalid character: {}", digit));
-                    }
-                    value = value * 10 + (digit as u32 - '0' as u32);
-                } else {
-                    break;
-                }
-            }
-            bits.append(&mut Bit::from(value, 10, false, true));
-            i += 3;
+    fn encode_numeric(data: &str, bits: &mut Vec<Bit>) -> Result<(), String> {
+        if let Some(c) = data.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(format!("Invalid character: {}", c));
         }
-        Ok(bits)
+
+        bits.reserve(data.len() / 3 * 10 + 7);
+        for group in data.as_bytes().chunks(3) {
+            let value = group
+                .iter()
+                .fold(0, |acc, digit| acc * 10 + (digit - b'0') as u32);
+            // 3 digits fit in 10 bits, 2 digits in 7 bits and a single digit in 4 bits
+            let n_bits = match group.len() {
+                3 => 10,
+                2 => 7,
+                _ => 4,
+            };
+            Bit::push_bits(bits, value, n_bits, ModuleRole::Empty);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_string(bits: &[Bit]) -> String {
+        bits.iter()
+            .map(|bit| if bit.value() { '1' } else { '0' })
+            .collect()
+    }
+
+    #[test]
+    fn encode_numeric_shortens_last_group() {
+        let bits = Encoding::Numeric.encode("01234567").unwrap();
+        assert_eq!(to_string(&bits), "000000110001010110011000011");
+    }
+
+    #[test]
+    fn encode_numeric_rejects_non_digits() {
+        let result = Encoding::Numeric.encode("12a");
+        assert_eq!(result.err(), Some("Invalid character: a".to_string()));
+    }
+
+    #[test]
+    fn encode_alphanumeric_works() {
+        let bits = Encoding::Alphanumeric.encode("HELLO WORLD").unwrap();
+        assert_eq!(
+            to_string(&bits),
+            "0110000101101111000110100010111001011011100010011010100001101"
+        );
     }
 }
@@ -1,7 +1,7 @@
-use crate::bit::Bit;
+use crate::bit::{Bit, ModuleRole};
 
 impl Bit {
-    pub fn bytes(data: &Vec<Bit>) -> Vec<u8> {
+    pub fn bytes(data: &[Bit]) -> Vec<u8> {
         data.chunks(8)
             .map(|chunk| {
                 let mut byte = 0u8;
@@ -13,21 +13,11 @@ impl Bit {
             .collect()
     }
 
-    pub fn bits(data: &Vec<u8>, size: usize) -> Vec<Bit> {
+    pub fn bits(data: &[u8], size: usize) -> Vec<Bit> {
         data.iter()
-            .map(|byte| {
-                (0..8)
-                    .map(|i| {
-                        let bit = (byte >> (7 - i)) & 1u8;
-                        match bit {
-                            1 => Bit::One(false),
-                            0 => Bit::Zero(false),
-                            _ => unreachable!(),
-                        }
-                    })
-                    .collect::<Vec<Bit>>()
+            .flat_map(|byte| {
+                (0..8).map(move |i| Bit::new((byte >> (7 - i)) & 1u8 == 1, ModuleRole::Empty))
             })
-            .flatten()
             .take(size)
             .collect()
     }
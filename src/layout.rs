@@ -0,0 +1,189 @@
+use crate::ec::{block_sizes, ec_codewords_per_block, EcLevel};
+use crate::encoding::Encoding;
+use crate::mask::MaskPattern;
+use crate::qrcode::QrCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodewordKind {
+    Data,
+    Ec,
+}
+
+/// Where a single codeword ends up in the symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodewordPosition {
+    pub kind: CodewordKind,
+    /// Block the codeword belongs to.
+    pub block: usize,
+    /// Index of the codeword within the data or EC part of its block.
+    pub index: usize,
+    /// `(x, y)` coordinates of the 8 modules, most significant bit first.
+    pub modules: [(u32, u32); 8],
+}
+
+/// Placement of every codeword of a symbol, in the interleaved order used by `QrCode::fill`.
+#[derive(Clone, Debug)]
+pub struct CodewordLayout {
+    pub codewords: Vec<CodewordPosition>,
+    /// Modules left after the last codeword, which are filled with remainder bits.
+    pub remainder: Vec<(u32, u32)>,
+}
+
+/// Computes the codeword layout of a version. The EC level is needed as it decides how the
+/// codewords are split into blocks.
+pub fn codeword_layout(version: u8, ec_level: EcLevel) -> Result<CodewordLayout, String> {
+    let mut template = QrCode::new(version, ec_level, MaskPattern::Checkerboard, Encoding::Byte)?;
+    template.all_functional_patterns();
+
+    let size = template.size();
+    let mut modules = template
+        .data_module_indices()
+        .into_iter()
+        .map(|index| (index % size, index / size));
+
+    let data_sizes = block_sizes(version, &ec_level);
+    let ec_size = ec_codewords_per_block(version, &ec_level);
+    let max_data_size = data_sizes.iter().copied().max().unwrap_or(0);
+
+    let data_order = (0..max_data_size).flat_map(|index| {
+        data_sizes
+            .iter()
+            .enumerate()
+            .filter(move |(_, &block_size)| index < block_size)
+            .map(move |(block, _)| (CodewordKind::Data, block, index))
+    });
+    let ec_order = (0..ec_size)
+        .flat_map(|index| (0..data_sizes.len()).map(move |block| (CodewordKind::Ec, block, index)));
+
+    let codewords = data_order
+        .chain(ec_order)
+        .map(|(kind, block, index)| {
+            let mut positions = [(0, 0); 8];
+            for position in positions.iter_mut() {
+                *position = modules.next().expect("codewords fit in the symbol");
+            }
+            CodewordPosition {
+                kind,
+                block,
+                index,
+                modules: positions,
+            }
+        })
+        .collect();
+
+    Ok(CodewordLayout {
+        codewords,
+        remainder: modules.collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::data_codewords_count;
+
+    #[test]
+    fn codeword_layout_covers_all_codewords() {
+        for version in 1..=40 {
+            let layout = codeword_layout(version, EcLevel::Q).unwrap();
+            let blocks = block_sizes(version, &EcLevel::Q).len();
+            let expected = data_codewords_count(version, &EcLevel::Q)
+                + blocks * ec_codewords_per_block(version, &EcLevel::Q);
+            assert_eq!(layout.codewords.len(), expected);
+        }
+    }
+
+    #[test]
+    fn codeword_layout_has_remainder_bits() {
+        let remainder = |version| {
+            codeword_layout(version, EcLevel::L)
+                .unwrap()
+                .remainder
+                .len()
+        };
+        assert_eq!(remainder(1), 0);
+        assert_eq!(remainder(2), 7);
+        assert_eq!(remainder(7), 0);
+        assert_eq!(remainder(14), 3);
+        assert_eq!(remainder(21), 4);
+        assert_eq!(remainder(40), 0);
+    }
+
+    #[test]
+    fn codeword_layout_starts_bottom_right() {
+        let layout = codeword_layout(1, EcLevel::M).unwrap();
+        let first = &layout.codewords[0];
+        assert_eq!(first.kind, CodewordKind::Data);
+        assert_eq!((first.block, first.index), (0, 0));
+        assert_eq!(
+            first.modules,
+            [
+                (20, 20),
+                (19, 20),
+                (20, 19),
+                (19, 19),
+                (20, 18),
+                (19, 18),
+                (20, 17),
+                (19, 17)
+            ]
+        );
+    }
+
+    #[test]
+    fn codeword_layout_interleaves_blocks() {
+        // version 5-Q has 2 blocks of 15 and 2 blocks of 16 data codewords
+        let layout = codeword_layout(5, EcLevel::Q).unwrap();
+        let order: Vec<_> = layout.codewords[..5]
+            .iter()
+            .map(|codeword| (codeword.block, codeword.index))
+            .collect();
+        assert_eq!(order, vec![(0, 0), (1, 0), (2, 0), (3, 0), (0, 1)]);
+
+        let last_data = layout
+            .codewords
+            .iter()
+            .rposition(|codeword| codeword.kind == CodewordKind::Data)
+            .unwrap();
+        assert_eq!(
+            (
+                layout.codewords[last_data].block,
+                layout.codewords[last_data].index
+            ),
+            (3, 15)
+        );
+    }
+
+    #[test]
+    fn codeword_layout_returns_error_for_invalid_version() {
+        assert!(codeword_layout(0, EcLevel::L).is_err());
+        assert!(codeword_layout(41, EcLevel::L).is_err());
+    }
+
+    #[test]
+    fn codeword_layout_matches_filled_roles() {
+        use crate::bit::{Bit, ModuleRole};
+
+        let layout = codeword_layout(10, EcLevel::H).unwrap();
+        let mut qrcode =
+            QrCode::new(10, EcLevel::H, MaskPattern::Diamonds, Encoding::Byte).unwrap();
+        qrcode.all_functional_patterns();
+        qrcode.fill(&vec![
+            Bit::Zero(ModuleRole::Empty);
+            layout.codewords.len() * 8
+        ]);
+
+        for (i, codeword) in layout.codewords.iter().enumerate() {
+            let expected = match codeword.kind {
+                CodewordKind::Data => ModuleRole::Data(i as u16),
+                CodewordKind::Ec => ModuleRole::Ec(i as u16),
+            };
+            for (x, y) in codeword.modules {
+                assert_eq!(qrcode.role(x, y), Some(expected));
+            }
+        }
+        for (x, y) in layout.remainder {
+            assert_eq!(qrcode.role(x, y), Some(ModuleRole::Remainder));
+        }
+    }
+}
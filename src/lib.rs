@@ -0,0 +1,13 @@
+pub mod bit;
+pub mod decode;
+pub mod ec;
+pub mod encoder;
+pub mod encoding;
+mod format;
+pub mod layout;
+pub mod mask;
+pub mod preprocessor;
+pub mod qrcode;
+pub mod render;
+mod tables;
+mod validate;
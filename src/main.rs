@@ -1,22 +1,16 @@
-use crate::ec::*;
-use crate::encoding::Encoding;
-use crate::mask::MaskPattern;
-use crate::preprocessor::Preprocessor;
-
-mod bit;
-mod debug_utils;
-mod ec;
-mod encoding;
-mod format;
-mod mask;
-mod preprocessor;
-mod qrcode;
-mod tables;
+use qrcode::ec::*;
+use qrcode::encoding::Encoding;
+use qrcode::mask::MaskPattern;
+use qrcode::preprocessor::Preprocessor;
+use qrcode::render::inline::{self, InlineOptions};
 
 fn main() {
     let data = "https://example.com https://example.com https://example.com";
     let preprocessor = Preprocessor::new(data, Encoding::Byte, EcLevel::H, MaskPattern::Diagonal);
     let qrcode = preprocessor.generate_qrcode();
 
-    println!("{}", qrcode);
+    print!(
+        "{}",
+        inline::render_for_stdout(&qrcode, &InlineOptions::default())
+    );
 }
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use crate::bit::Bit;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MaskPattern {
     Checkerboard,
     Horizontal,
@@ -11,13 +13,24 @@ pub enum MaskPattern {
 }
 
 impl MaskPattern {
+    pub const ALL: [MaskPattern; 8] = [
+        MaskPattern::Checkerboard,
+        MaskPattern::Horizontal,
+        MaskPattern::Vertical,
+        MaskPattern::Diagonal,
+        MaskPattern::LargeCheckerboard,
+        MaskPattern::Fields,
+        MaskPattern::Diamonds,
+        MaskPattern::Meadow,
+    ];
+
     pub fn get_mask(&self) -> fn(u32, u32) -> bool {
         match self {
             MaskPattern::Checkerboard => |x, y| (x + y) % 2 == 0,
             MaskPattern::Horizontal => |_, y| y % 2 == 0,
             MaskPattern::Vertical => |x, _| x % 3 == 0,
             MaskPattern::Diagonal => |x, y| (x + y) % 3 == 0,
-            MaskPattern::LargeCheckerboard => |x, y| ((x / 2) + (y / 3)) % 2 == 0,
+            MaskPattern::LargeCheckerboard => |x, y| ((y / 2) + (x / 3)) % 2 == 0,
             MaskPattern::Fields => |x, y| ((x * y) % 2) + ((x * y) % 3) == 0,
             MaskPattern::Diamonds => |x, y| (((x * y) % 2) + ((x * y) % 3)) % 2 == 0,
             MaskPattern::Meadow => |x, y| (((x + y) % 2) + ((x * y) % 3)) % 2 == 0,
@@ -37,3 +50,92 @@ impl MaskPattern {
         }
     }
 }
+
+/// Penalty score of a masked symbol, the mask with the lowest score should be used. Modules
+/// outside the symbol count as light, like the quiet zone.
+pub fn penalty(modules: &[Bit], size: usize) -> u32 {
+    let dark: Vec<bool> = modules.iter().map(|module| module.value()).collect();
+    let mut score = 0;
+
+    // rows then columns
+    for transpose in [false, true] {
+        for i in 0..size {
+            let module = |j: usize| {
+                if transpose {
+                    dark[i + size * j]
+                } else {
+                    dark[j + size * i]
+                }
+            };
+            // lengths of the last 7 runs, the latest first, the quiet zone extends the outer ones
+            let mut runs = [0; 7];
+            let mut color = false;
+            let mut run = 0;
+            for j in 0..size {
+                if module(j) == color {
+                    // runs of 5 or more modules of the same color
+                    run += 1;
+                    if run == 5 {
+                        score += 3;
+                    } else if run > 5 {
+                        score += 1;
+                    }
+                } else {
+                    push_run(&mut runs, run, size);
+                    if !color {
+                        score += 40 * finder_like(&runs);
+                    }
+                    color = module(j);
+                    run = 1;
+                }
+            }
+            if color {
+                push_run(&mut runs, run, size);
+                run = 0;
+            }
+            push_run(&mut runs, run + size, size);
+            score += 40 * finder_like(&runs);
+        }
+    }
+
+    // 2x2 blocks of the same color
+    for y in 0..size - 1 {
+        for x in 0..size - 1 {
+            let index = x + size * y;
+            let color = dark[index];
+            if dark[index + 1] == color
+                && dark[index + size] == color
+                && dark[index + size + 1] == color
+            {
+                score += 3;
+            }
+        }
+    }
+
+    // 10 points per 5% step away from an even balance of dark and light modules
+    let dark_count = dark.iter().filter(|&&module| module).count();
+    let total = dark.len();
+    let step = (dark_count * 20)
+        .abs_diff(total * 10)
+        .div_ceil(total)
+        .saturating_sub(1);
+    score += 10 * step as u32;
+
+    score
+}
+
+fn push_run(runs: &mut [usize; 7], length: usize, size: usize) {
+    // the first run of a line starts in the quiet zone
+    let length = if runs[0] == 0 { length + size } else { length };
+    runs.rotate_right(1);
+    runs[0] = length;
+}
+
+/// Number of dark-light-dark-dark-dark-light-dark patterns at any scale ending the runs, with 4
+/// light modules on one side.
+fn finder_like(runs: &[usize; 7]) -> u32 {
+    let n = runs[1];
+    let core = n > 0 && runs[2] == n && runs[3] == 3 * n && runs[4] == n && runs[5] == n;
+    (core && runs[0] >= 4 * n && runs[6] >= n) as u32
+        + (core && runs[6] >= 4 * n && runs[0] >= n) as u32
+}
@@ -1,12 +1,11 @@
-use crate::bit::Bit;
-use crate::debug_vec;
+use crate::bit::{Bit, ModuleRole};
 use crate::ec::*;
 use crate::encoding::*;
 use crate::mask::MaskPattern;
 use crate::qrcode::QrCode;
 use crate::tables::{
-    ALPHANUMERIC_CHAR_COUNT, ALPHANUMERIC_SIZE, BYTE_CHAR_COUNT, BYTE_SIZE, DATA_BYTES_PER_BLOCK,
-    EC_BYTES_PER_BLOCK, KANJI_CHAR_COUNT, KANJI_SIZE, NUMERIC_CHAR_COUNT, NUMERIC_SIZE,
+    ALPHANUMERIC_CHAR_COUNT, ALPHANUMERIC_SIZE, BYTE_CHAR_COUNT, BYTE_SIZE, KANJI_CHAR_COUNT,
+    KANJI_SIZE, NUMERIC_CHAR_COUNT, NUMERIC_SIZE,
 };
 
 pub struct Preprocessor {
@@ -40,8 +39,28 @@ impl Preprocessor {
         ec_level: EcLevel,
         mask_pattern: MaskPattern,
     ) -> Preprocessor {
-        let mut bits = encoding.encode(data).unwrap();
+        let mut data_bits = Vec::new();
+        let version = Self::encode_into(data, encoding, ec_level, &mut Vec::new(), &mut data_bits)
+            .unwrap_or_else(|err| panic!("{}", err));
 
+        Preprocessor {
+            qrcode_bits: data_bits,
+            encoding,
+            ec_level,
+            version,
+            mask_pattern,
+        }
+    }
+
+    /// Writes the bits to place in the symbol (data then EC codewords) into `out`, using `segment`
+    /// as scratch space. Returns the smallest version that can hold `data`.
+    pub(crate) fn encode_into(
+        data: &str,
+        encoding: Encoding,
+        ec_level: EcLevel,
+        segment: &mut Vec<Bit>,
+        out: &mut Vec<Bit>,
+    ) -> Result<u8, String> {
         let table = Self::table_from_encoding(encoding);
 
         let (v, _) = table
@@ -50,81 +69,55 @@ impl Preprocessor {
             .step_by(4)
             .enumerate()
             .find(|(_, &size)| data.len() <= size as usize)
-            .expect("Not enough space.");
-
-        let version = v + 1;
+            .ok_or("Not enough space.")?;
 
-        let char_count = Self::char_count(version as u8, encoding);
-        let mut char_count_to_bits = Bit::from(data.len() as u32, char_count, false, true);
+        let version = (v + 1) as u8;
 
-        if bits.len() < char_count as usize {
-            bits.append(&mut vec![
-                Bit::Zero(false);
-                (char_count as usize) - bits.len()
-            ]);
-        }
+        let char_count = Self::char_count(version, encoding);
 
-        let mut data_segment = Vec::new();
-        let mut mod_indicator_bits = encoding.mod_indicator();
+        segment.clear();
+        segment.extend(encoding.mod_indicator());
+        Bit::push_bits(segment, data.len() as u32, char_count, ModuleRole::Empty);
 
-        data_segment.append(&mut mod_indicator_bits);
-        data_segment.append(&mut char_count_to_bits);
-        data_segment.append(&mut bits);
+        encoding.encode_into(data, segment)?;
 
         // Compute total size without ec bits
-        let (block_1_size, block_1_count, block_2_size, block_2_count) =
-            DATA_BYTES_PER_BLOCK[version - 1][ec_level.ordinal() as usize];
-        let total_data_bits = (block_1_size * block_1_count + block_2_size * block_2_count) * 8;
+        let total_data_bits = data_codewords_count(version, &ec_level) * 8;
 
         // Add terminator bits (at most 4 0s)
-        if data_segment.len() < total_data_bits {
-            let empty_bits = total_data_bits - data_segment.len();
-            if empty_bits >= 4 {
-                data_segment.append(&mut vec![Bit::Zero(false); 4]);
-            } else {
-                data_segment.append(&mut vec![Bit::Zero(false); empty_bits]);
-            }
+        if segment.len() < total_data_bits {
+            let empty_bits = total_data_bits - segment.len();
+            segment.resize(
+                segment.len() + empty_bits.min(4),
+                Bit::Zero(ModuleRole::Empty),
+            );
         }
 
         // Add padding bits
-        while data_segment.len() % 8 != 0 {
-            data_segment.push(Bit::Zero(false));
-        }
+        segment.resize(
+            segment.len().next_multiple_of(8),
+            Bit::Zero(ModuleRole::Empty),
+        );
 
         // Add padding bytes
-        if data_segment.len() < total_data_bits {
-            let mut byte_1 = to_bits_array(&[236]);
-            let mut byte_2 = to_bits_array(&[17]);
-
-            while data_segment.len() < total_data_bits {
-                data_segment.extend_from_slice(&byte_1);
-                std::mem::swap(&mut byte_1, &mut byte_2);
+        for pad_byte in [236, 17].iter().cycle() {
+            if segment.len() >= total_data_bits {
+                break;
             }
+            Bit::push_bits(segment, *pad_byte, 8, ModuleRole::Empty);
         }
 
-        let cw_per_block = EC_BYTES_PER_BLOCK[version - 1][ec_level.ordinal() as usize];
-
-        let (data_codewords, ec_codewords) = codewords(
-            &Bit::bytes(&data_segment),
-            version as u8,
-            &ec_level,
-            cw_per_block,
-        );
-
-        let mut data_bits = Bit::bits(&data_codewords, data_codewords.len() * 8);
-        let error_correction = Bit::bits(&ec_codewords, ec_codewords.len() * 8);
+        let cw_per_block = ec_codewords_per_block(version, &ec_level);
 
-        data_bits.extend(error_correction);
+        let (data_codewords, ec_codewords) =
+            codewords(&Bit::bytes(segment), version, &ec_level, cw_per_block);
 
-        debug_vec!(&data_bits);
-
-        Preprocessor {
-            qrcode_bits: data_bits,
-            encoding,
-            ec_level,
-            version: version as u8,
-            mask_pattern,
+        out.clear();
+        for codeword in data_codewords.iter().chain(ec_codewords.iter()) {
+            Bit::push_bits(out, *codeword as u32, 8, ModuleRole::Empty);
         }
+
+        Ok(version)
     }
 
     fn table_from_encoding(encoding: Encoding) -> &'static [u32; 160] {
@@ -136,7 +129,7 @@ impl Preprocessor {
         }
     }
 
-    fn char_count(version: u8, encoding: Encoding) -> u8 {
+    pub(crate) fn char_count(version: u8, encoding: Encoding) -> u8 {
         let index = match version {
             1_u8..=9_u8 => 0,
             10_u8..=26_u8 => 1,
@@ -151,3 +144,219 @@ impl Preprocessor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PI: &str = "3141592653589793238462643383279502884197169399375105820974944592307816406286";
+
+    // reference symbols generated with the qrcodegen crate, dark modules are `#`
+    #[rustfmt::skip]
+    const NUMERIC_1_M_MASK_4: [&str; 21] = [
+        "#######.#..##.#######",
+        "#.....#...###.#.....#",
+        "#.###.#...###.#.###.#",
+        "#.###.#.#####.#.###.#",
+        "#.###.#.#####.#.###.#",
+        "#.....#.##..#.#.....#",
+        "#######.#.#.#.#######",
+        "........#.#..........",
+        "#...#.###...######..#",
+        ".##..#...##.###..####",
+        "#.#.####.##.##.....##",
+        "#....#...####..#.....",
+        ".##.###..#.#..###..##",
+        "........#####..#.####",
+        "#######.##.#..#####..",
+        "#.....#......##.##..#",
+        "#.###.#.##..###..####",
+        "#.###.#.....###....##",
+        "#.###.#.....##...#...",
+        "#.....#...###..#.#.#.",
+        "#######.#.##..###.###",
+    ];
+
+    #[rustfmt::skip]
+    const ALPHANUMERIC_1_Q_MASK_6: [&str; 21] = [
+        "#######....#..#######",
+        "#.....#.##..#.#.....#",
+        "#.###.#..#.##.#.###.#",
+        "#.###.#.#####.#.###.#",
+        "#.###.#.##.#..#.###.#",
+        "#.....#..#..#.#.....#",
+        "#######.#.#.#.#######",
+        "........##.##........",
+        ".#.####.##..###.##.#.",
+        "#.####.#....####.###.",
+        "..#.#.##...#..##.....",
+        "#.##.#...#.##...##...",
+        "##.########.###.#####",
+        "........#...#..#.#...",
+        "#######..##..##..####",
+        "#.....#.#.#..#..#.###",
+        "#.###.#.##.#..#...###",
+        "#.###.#.#.###...#.#..",
+        "#.###.#..#....#....##",
+        "#.....#.###..###..##.",
+        "#######..#.#.......#.",
+    ];
+
+    #[rustfmt::skip]
+    const NUMERIC_2_L_MASK_3: [&str; 25] = [
+        "#######.##.##.#...#######",
+        "#.....#.....###.#.#.....#",
+        "#.###.#.#.#.#.###.#.###.#",
+        "#.###.#.#..###..#.#.###.#",
+        "#.###.#.#..#.###..#.###.#",
+        "#.....#..#..#...#.#.....#",
+        "#######.#.#.#.#.#.#######",
+        "..........#..#.#.........",
+        "####..#.#..##.##.#..###.#",
+        "#.##...###..##.#..##..##.",
+        "#.#...#####.###..#...#...",
+        "...###.##.##.#..#.#.#.#.#",
+        "#.##.##..#....#...#..#..#",
+        ".#...#.###...#..#..##.###",
+        ".#..####..##..#.##..###.#",
+        "#....#.#.##..##.#####..#.",
+        "..#.#.#..##.##..######.#.",
+        "........##.....##...#..#.",
+        "#######....#....#.#.#...#",
+        "#.....#.........#...#.#.#",
+        "#.###.#...####.#######...",
+        "#.###.#.####.##.#.#...###",
+        "#.###.#.#.###.#####.##.#.",
+        "#.....#.##...#.###....##.",
+        "#######.###.####.###..#.#",
+    ];
+
+    const NUMERIC_1_H_MASK_1: [&str; 21] = [
+        "#######...#...#######",
+        "#.....#.#.#...#.....#",
+        "#.###.#.#...#.#.###.#",
+        "#.###.#.##..#.#.###.#",
+        "#.###.#.##.#..#.###.#",
+        "#.....#.###...#.....#",
+        "#######.#.#.#.#######",
+        ".........####........",
+        "..#..#####..##.#####.",
+        "#..###....#..###.##..",
+        "..##.####....#####.##",
+        "#.###....###...#.##..",
+        "#.##..#####....####..",
+        "........##..#......#.",
+        "#######.#..#.##...#.#",
+        "#.....#.#####...#....",
+        "#.###.#....##.#...###",
+        "#.###.#..#...###.##..",
+        "#.###.#.##.#.#.###.##",
+        "#.....#...#.####.###.",
+        "#######..#...########",
+    ];
+
+    fn assert_symbol(
+        data: &str,
+        encoding: Encoding,
+        ec_level: EcLevel,
+        mask: MaskPattern,
+        expected: &[&str],
+    ) {
+        let qrcode = Preprocessor::new(data, encoding, ec_level, mask).generate_qrcode();
+        let modules: String = qrcode
+            .data
+            .iter()
+            .map(|bit| if bit.value() { '#' } else { '.' })
+            .collect();
+        assert_eq!(modules, expected.concat());
+    }
+
+    #[test]
+    fn numeric_symbol_matches_reference() {
+        // the last group of 2 digits, mask pattern 4
+        assert_symbol(
+            "01234567",
+            Encoding::Numeric,
+            EcLevel::M,
+            MaskPattern::LargeCheckerboard,
+            &NUMERIC_1_M_MASK_4,
+        );
+    }
+
+    #[test]
+    fn short_segment_is_not_padded() {
+        // the terminator follows the 4 data bits of a single digit right away
+        assert_symbol(
+            "7",
+            Encoding::Numeric,
+            EcLevel::H,
+            MaskPattern::Horizontal,
+            &NUMERIC_1_H_MASK_1,
+        );
+    }
+
+    #[test]
+    fn alphanumeric_symbol_matches_reference() {
+        assert_symbol(
+            "HELLO WORLD",
+            Encoding::Alphanumeric,
+            EcLevel::Q,
+            MaskPattern::Diamonds,
+            &ALPHANUMERIC_1_Q_MASK_6,
+        );
+    }
+
+    #[test]
+    fn symbol_with_alignment_pattern_matches_reference() {
+        // a last group of 1 digit, and data flowing around the alignment pattern and the timing
+        // column
+        assert_symbol(
+            PI,
+            Encoding::Numeric,
+            EcLevel::L,
+            MaskPattern::Diagonal,
+            &NUMERIC_2_L_MASK_3,
+        );
+    }
+
+    /// Mask picked by `apply_best_mask` for a symbol generated with `mask`.
+    fn best_mask(data: &str, encoding: Encoding, ec_level: EcLevel, mask: MaskPattern) -> QrCode {
+        let mut qrcode = Preprocessor::new(data, encoding, ec_level, mask).generate_qrcode();
+        // masking twice restores the unmasked symbol
+        qrcode.apply_mask();
+        qrcode.apply_best_mask();
+        qrcode
+    }
+
+    #[test]
+    fn best_mask_matches_reference() {
+        // masks picked by the qrcodegen crate
+        let cases = [
+            ("01234567", Encoding::Numeric, EcLevel::M, 0),
+            ("HELLO WORLD", Encoding::Alphanumeric, EcLevel::Q, 0),
+            (PI, Encoding::Numeric, EcLevel::L, 1),
+        ];
+        for (data, encoding, ec_level, expected) in cases {
+            let qrcode = best_mask(data, encoding, ec_level, MaskPattern::Checkerboard);
+            assert_eq!(qrcode.mask_pattern().ordinal(), expected, "{}", data);
+        }
+    }
+
+    #[test]
+    fn best_mask_redraws_format_information() {
+        let qrcode = best_mask(
+            "HELLO WORLD",
+            Encoding::Alphanumeric,
+            EcLevel::Q,
+            MaskPattern::Diamonds,
+        );
+        let expected = Preprocessor::new(
+            "HELLO WORLD",
+            Encoding::Alphanumeric,
+            EcLevel::Q,
+            MaskPattern::Checkerboard,
+        )
+        .generate_qrcode();
+        assert!(qrcode.data == expected.data);
+    }
+}
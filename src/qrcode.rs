@@ -1,10 +1,13 @@
-use crate::bit::Bit;
-use crate::ec::EcLevel;
+use crate::bit::{Bit, ModuleRole};
+use crate::ec::{data_codewords_count, EcLevel};
 use crate::encoding::Encoding;
+use crate::mask;
 use crate::mask::MaskPattern;
+use crate::render::text::{self, TextOptions};
 use std::fmt;
 use std::fmt::Formatter;
 
+#[derive(Clone)]
 pub struct QrCode {
     pub data: Vec<Bit>,
     version: u8,
@@ -14,7 +17,8 @@ pub struct QrCode {
 }
 
 impl QrCode {
-    fn get(&self, x: u32, y: u32) -> Option<Bit> {
+    /// Module at `(x, y)`, `None` when out of the symbol.
+    pub fn get(&self, x: u32, y: u32) -> Option<Bit> {
         if let Some(index) = self.coords_to_index_from_instance(x, y) {
             self.data.get(index as usize).copied()
         } else {
@@ -22,6 +26,11 @@ impl QrCode {
         }
     }
 
+    /// Role of the module at `(x, y)`, `None` when out of the symbol.
+    pub fn role(&self, x: u32, y: u32) -> Option<ModuleRole> {
+        self.get(x, y).map(|bit| bit.role())
+    }
+
     fn put(&mut self, x: u32, y: u32, data: Bit) {
         if let Some(index) = self.coords_to_index_from_instance(x, y) {
             self.data[index as usize] = data;
@@ -36,10 +45,6 @@ impl QrCode {
         }
     }
 
-    fn coords_to_index_from_version(x: u32, y: u32, version: u8) -> Option<u32> {
-        Self::coords_to_index(x, y, Self::size_from_version(version))
-    }
-
     fn coords_to_index_from_instance(&self, x: u32, y: u32) -> Option<u32> {
         Self::coords_to_index(x, y, self.size())
     }
@@ -54,7 +59,7 @@ impl QrCode {
             Err("Invalid version.".to_string())
         } else {
             let size = Self::size_from_version(version);
-            let data = vec![Bit::Zero(false); (size * size) as usize];
+            let data = vec![Bit::Zero(ModuleRole::Empty); (size * size) as usize];
             Ok(QrCode {
                 data,
                 version,
@@ -69,20 +74,39 @@ impl QrCode {
         17 + 4 * version as u32
     }
 
-    fn size(&self) -> u32 {
+    pub fn size(&self) -> u32 {
         Self::size_from_version(self.version)
     }
 
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn ec_level(&self) -> EcLevel {
+        self.ec_level
+    }
+
+    pub fn mask_pattern(&self) -> MaskPattern {
+        self.mask_pattern
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
     fn finder_patterns(&mut self) {
+        const D: Bit = Bit::One(ModuleRole::Finder);
+        const L: Bit = Bit::Zero(ModuleRole::Finder);
+
         #[rustfmt::skip]
         const FINDER_PATTERN: [Bit; 49] = [
-            Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true),
-            Bit::One(true), Bit::Zero(true), Bit::Zero(true), Bit::Zero(true), Bit::Zero(true), Bit::Zero(true), Bit::One(true),
-            Bit::One(true), Bit::Zero(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::Zero(true), Bit::One(true),
-            Bit::One(true), Bit::Zero(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::Zero(true), Bit::One(true),
-            Bit::One(true), Bit::Zero(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::Zero(true), Bit::One(true),
-            Bit::One(true), Bit::Zero(true), Bit::Zero(true), Bit::Zero(true), Bit::Zero(true), Bit::Zero(true), Bit::One(true),
-            Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true), Bit::One(true),
+            D, D, D, D, D, D, D,
+            D, L, L, L, L, L, D,
+            D, L, D, D, D, L, D,
+            D, L, D, D, D, L, D,
+            D, L, D, D, D, L, D,
+            D, L, L, L, L, L, D,
+            D, D, D, D, D, D, D,
         ];
 
         const PATTERN_LENGTH: u32 = 7;
@@ -111,13 +135,13 @@ impl QrCode {
 
         for (x, y) in top {
             for dy in 0..8 {
-                self.put(x, y + dy, Bit::Zero(true))
+                self.put(x, y + dy, Bit::Zero(ModuleRole::Separator))
             }
         }
 
         for (x, y) in right {
             for dx in 0..7 {
-                self.put(x + dx, y, Bit::Zero(true))
+                self.put(x + dx, y, Bit::Zero(ModuleRole::Separator))
             }
         }
     }
@@ -127,7 +151,7 @@ impl QrCode {
 
         for elem1 in array {
             for elem2 in array {
-                res.push((elem1.clone(), elem2.clone()));
+                res.push((*elem1, *elem2));
             }
         }
 
@@ -141,31 +165,31 @@ impl QrCode {
         const PATTERN_LENGTH: u32 = 5;
 
         const ALIGNMENT_PATTERN: [Bit; 25] = [
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::Zero(true),
-            Bit::Zero(true),
-            Bit::Zero(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::Zero(true),
-            Bit::One(true),
-            Bit::Zero(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::Zero(true),
-            Bit::Zero(true),
-            Bit::Zero(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
-            Bit::One(true),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::Zero(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
+            Bit::One(ModuleRole::Alignment),
         ];
 
         if !self.get(x, y).unwrap().is_functional() {
@@ -181,7 +205,8 @@ impl QrCode {
         }
     }
 
-    fn alignment_patterns(&mut self) {
+    /// Centers of the alignment patterns of a version, leaving out the ones overlapping finders.
+    pub fn alignment_pattern_centers(version: u8) -> Vec<(u32, u32)> {
         const COORDS: [&[u8]; 39] = [
             &[6, 18],
             &[6, 22],
@@ -224,11 +249,24 @@ impl QrCode {
             &[6, 30, 58, 86, 114, 142, 170],
         ];
 
-        if self.version != 1 {
-            let combinations = Self::combination(COORDS[(self.version - 2) as usize]);
-            for (x, y) in combinations {
-                self.draw_alignment_pattern(x as u32, y as u32);
-            }
+        if version == 1 {
+            return vec![];
+        }
+
+        let coords = COORDS[(version - 2) as usize];
+        let (first, last) = (coords[0], coords[coords.len() - 1]);
+        Self::combination(coords)
+            .into_iter()
+            .filter(|&corner| {
+                corner != (first, first) && corner != (first, last) && corner != (last, first)
+            })
+            .map(|(x, y)| (x as u32, y as u32))
+            .collect()
+    }
+
+    fn alignment_patterns(&mut self) {
+        for (x, y) in Self::alignment_pattern_centers(self.version) {
+            self.draw_alignment_pattern(x, y);
         }
     }
 
@@ -244,9 +282,9 @@ impl QrCode {
             }
 
             if x % 2 == 0 {
-                bit = Bit::One(true);
+                bit = Bit::One(ModuleRole::Timing);
             } else {
-                bit = Bit::Zero(true);
+                bit = Bit::Zero(ModuleRole::Timing);
             }
 
             self.put(x, 6, bit);
@@ -260,9 +298,9 @@ impl QrCode {
             }
 
             if y % 2 == 0 {
-                bit = Bit::One(true);
+                bit = Bit::One(ModuleRole::Timing);
             } else {
-                bit = Bit::Zero(true);
+                bit = Bit::Zero(ModuleRole::Timing);
             }
 
             self.put(6, y, bit);
@@ -270,7 +308,11 @@ impl QrCode {
     }
 
     fn dark_module(&mut self) {
-        self.put(8, (4 * self.version + 9) as u32, Bit::One(true))
+        self.put(
+            8,
+            (4 * self.version + 9) as u32,
+            Bit::One(ModuleRole::DarkModule),
+        )
     }
 
     fn format_information(&mut self) {
@@ -280,45 +322,26 @@ impl QrCode {
             0x2EDA, 0x2BED, 0x1689, 0x13BE, 0x1CE7, 0x19D0, 0x762, 0x255, 0xD0C, 0x83B,
         ];
 
-        let mut index = self.mask_pattern.ordinal() as u32;
-        match self.ec_level {
-            EcLevel::L => index += 0,
-            EcLevel::M => index += 8 * 1,
-            EcLevel::Q => index += 8 * 2,
-            EcLevel::H => index += 8 * 3,
-        }
+        let index = self.mask_pattern.ordinal() as u32 + 8 * self.ec_level.ordinal() as u32;
 
         let info_bit = FORMAT_BITS[index as usize];
-        let bits = Bit::from(info_bit, 15, true, true);
+        let bits = Bit::from(info_bit, 15, ModuleRole::FormatInfo, true);
 
-        let mut i = 0;
-        for x in 0..9 {
-            if self.get(x, 8).unwrap().is_functional() {
-                continue;
-            }
-            self.put(x, 8, bits[i as usize]);
-            i += 1;
+        // skip the timing patterns, so the format can be redrawn when the mask changes
+        for (i, x) in (0..9).filter(|&x| x != 6).enumerate() {
+            self.put(x, 8, bits[i]);
         }
 
-        let mut i = 8;
-        for y in (0..8).rev() {
-            if self.get(8, y).unwrap().is_functional() {
-                continue;
-            }
-            self.put(8, y, bits[i as usize]);
-            i += 1;
+        for (i, y) in (0..8).rev().filter(|&y| y != 6).enumerate() {
+            self.put(8, y, bits[i + 8]);
         }
 
-        let mut i = 0;
-        for y in ((self.size() - 7)..self.size()).rev() {
-            self.put(8, y, bits[i as usize]);
-            i += 1;
+        for (i, y) in ((self.size() - 7)..self.size()).rev().enumerate() {
+            self.put(8, y, bits[i]);
         }
 
-        let mut i = 7;
-        for x in (self.size() - 8)..self.size() {
-            self.put(x, 8, bits[i as usize]);
-            i += 1;
+        for (i, x) in ((self.size() - 8)..self.size()).enumerate() {
+            self.put(x, 8, bits[i + 7]);
         }
     }
 
@@ -336,7 +359,7 @@ impl QrCode {
         ];
 
         let version_bits = VERSION_BITS[(self.version - 7) as usize];
-        let bits = Bit::from(version_bits, 18, true, false);
+        let bits = Bit::from(version_bits, 18, ModuleRole::VersionInfo, false);
 
         // bottom left
         let mut x = 0;
@@ -365,19 +388,55 @@ impl QrCode {
 
     pub fn apply_mask(&mut self) {
         let mask_fn = self.mask_pattern.get_mask();
+        let size = self.size();
 
-        for x in 0..self.size() {
-            for y in 0..self.size() {
-                let bit = self.get(x, y).unwrap();
-                if !bit.is_functional() {
-                    if mask_fn(x, y) {
-                        self.put(x, y, bit.invert());
-                    }
-                }
+        for (index, bit) in self.data.iter_mut().enumerate() {
+            let (x, y) = (index as u32 % size, index as u32 / size);
+            if !bit.is_functional() && mask_fn(x, y) {
+                *bit = bit.invert();
+            }
+        }
+    }
+
+    /// Same as `apply_mask`, restricted to the modules at `indices` from `data_module_indices`.
+    pub(crate) fn apply_mask_modules(&mut self, indices: &[u32]) {
+        let mask_fn = self.mask_pattern.get_mask();
+        let size = self.size();
+
+        for &index in indices {
+            if mask_fn(index % size, index / size) {
+                let bit = &mut self.data[index as usize];
+                *bit = bit.invert();
             }
         }
     }
 
+    /// Applies the mask pattern with the lowest penalty score, instead of the one given to `new`.
+    /// Must be called on a filled symbol that is not masked yet.
+    pub fn apply_best_mask(&mut self) {
+        let size = self.size() as usize;
+        let mut best: Option<(u32, QrCode)> = None;
+
+        for mask_pattern in MaskPattern::ALL {
+            let mut candidate = self.clone();
+            candidate.mask_pattern = mask_pattern;
+            candidate.format_information();
+            candidate.apply_mask();
+
+            let score = mask::penalty(&candidate.data, size);
+            if best
+                .as_ref()
+                .is_none_or(|(best_score, _)| score < *best_score)
+            {
+                best = Some((score, candidate));
+            }
+        }
+
+        if let Some((_, qrcode)) = best {
+            *self = qrcode;
+        }
+    }
+
     pub fn all_functional_patterns(&mut self) {
         self.finder_patterns();
         self.separators_patterns();
@@ -390,113 +449,99 @@ impl QrCode {
         }
     }
 
-    pub fn fill(&mut self, bits: &Vec<Bit>) {
-        let n = self.size() as isize;
-        let mut bit_iter = bits.iter();
+    pub fn fill(&mut self, bits: &[Bit]) {
+        let indices = self.data_module_indices();
+        self.fill_modules(&indices, bits);
+    }
+
+    /// Writes `bits` into the modules at `indices`, which must come from `data_module_indices`.
+    /// Modules are tagged with the codeword they belong to, the ones left over as remainder bits.
+    pub(crate) fn fill_modules(&mut self, indices: &[u32], bits: &[Bit]) {
+        let data_codewords = data_codewords_count(self.version, &self.ec_level);
+
+        for (i, &index) in indices.iter().enumerate() {
+            let module = &mut self.data[index as usize];
+            *module = match bits.get(i) {
+                Some(bit) => {
+                    let codeword = i / 8;
+                    if codeword < data_codewords {
+                        bit.with_role(ModuleRole::Data(codeword as u16))
+                    } else {
+                        bit.with_role(ModuleRole::Ec(codeword as u16))
+                    }
+                }
+                None => module.with_role(ModuleRole::Remainder),
+            };
+        }
+    }
+
+    /// Indices in `data` of the modules that receive data, in placement order.
+    /// Functional patterns must be drawn first.
+    pub fn data_module_indices(&self) -> Vec<u32> {
+        let n = self.size() as usize;
+        let mut indices = Vec::with_capacity(n * n);
+        // Right column of the current 2-column block.
         let mut col = n - 1;
         // Direction: true means going upward (from bottom to top),
         // false means going downward (from top to bottom).
         let mut upward = true;
 
-        while col >= 0 {
-            // Determine how many columns to process in this block.
-            // Normally two columns, but if only one remains, process one.
-            let cols_to_process = if col == 0 { 1 } else { 2 };
-
-            if upward {
-                // Imagine starting at the bottom of these columns and moving upward.
-                for row in (0..n).rev() {
-                    for offset in 0..cols_to_process {
-                        let x = col - offset;
-                        // Visual: Picture the snake’s head reaching a cell.
-                        // If this cell is column 6 (the vertical timing pattern), skip it.
-                        if x == 6 {
-                            continue;
-                        }
-                        // If this cell isn't already a functional bit, fill it.
-                        if !self.get(x as u32, row as u32).unwrap().is_functional() {
-                            if let Some(bit) = bit_iter.next() {
-                                self.put(x as u32, row as u32, *bit);
-                            } else {
-                                return;
-                            }
-                        }
-                    }
-                }
-            } else {
-                // When going downward, imagine the snake slithering from the top down.
-                for row in 0..n {
-                    for offset in 0..cols_to_process {
-                        let x = col - offset;
-                        if x == 6 {
-                            continue;
-                        }
-                        if !self.get(x as u32, row as u32).unwrap().is_functional() {
-                            if let Some(bit) = bit_iter.next() {
-                                self.put(x as u32, row as u32, *bit);
-                            } else {
-                                return;
-                            }
-                        }
+        loop {
+            for i in 0..n {
+                let row = if upward { n - 1 - i } else { i };
+                for x in [col, col - 1] {
+                    // Only modules that are not part of a functional pattern receive data.
+                    let index = x + n * row;
+                    if !self.data[index].is_functional() {
+                        indices.push(index as u32);
                     }
                 }
             }
 
+            if col == 1 {
+                return indices;
+            }
+
             // Alternate the vertical direction for the next 2-column block.
             upward = !upward;
-            // Move to the next set of columns to the left.
-            col -= cols_to_process;
+            // Move to the next set of columns to the left, stepping over the
+            // vertical timing pattern in column 6 as a whole.
+            col -= 2;
+            if col == 6 {
+                col = 5;
+            }
         }
     }
+
+    /// Changes the EC level, mask pattern and encoding of a symbol and redraws the format
+    /// information, so a drawn template can be reused for another symbol of the same version.
+    pub(crate) fn reconfigure(
+        &mut self,
+        ec_level: EcLevel,
+        mask_pattern: MaskPattern,
+        encoding: Encoding,
+    ) {
+        self.ec_level = ec_level;
+        self.mask_pattern = mask_pattern;
+        self.encoding = encoding;
+        self.format_information();
+    }
 }
 
 impl fmt::Display for QrCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut qrcode = String::new();
-
-        let lines: Vec<_> = self.data.chunks(self.size() as usize).collect();
-
-        // add quiet zone
-        for _ in 0..4 {
-            for _ in 0..self.size() + 8 {
-                qrcode.push_str("██");
-            }
-            qrcode.push('\n');
-        }
-
-        for i in 0..self.size() {
-            for _ in 0..4 {
-                qrcode.push_str("██");
-            }
-            for module in lines[i as usize] {
-                if module.value() {
-                    qrcode.push_str("  ");
-                } else {
-                    qrcode.push_str("██");
-                }
-            }
-            for _ in 0..4 {
-                qrcode.push_str("██");
-            }
-            qrcode.push('\n');
-        }
-
-        for _ in 0..4 {
-            for _ in 0..self.size() + 8 {
-                qrcode.push_str("██");
-            }
-            qrcode.push('\n');
-        }
-
-        let mut version = String::from('\n');
-        for _ in 0..self.size() {
-            version.push(' ');
-        }
-        version.push_str("Version: ");
-        version.push_str(self.version.to_string().as_str());
-        version.push('\n');
-
-        write!(f, "{}{}", qrcode, version)
+        let options = TextOptions {
+            quiet_zone: 4,
+            invert: true,
+        };
+        let caption = " ".repeat(self.size() as usize);
+        write!(
+            f,
+            "{}\n{}Version: {}\n",
+            text::blocks(self, &options),
+            caption,
+            self.version
+        )
     }
 }
 
@@ -580,10 +625,7 @@ mod tests {
             Encoding::Alphanumeric,
         )
         .unwrap();
-        match qr.ec_level {
-            EcLevel::Q => assert!(true),
-            _ => assert!(false, "Expected EcLevel::Q"),
-        }
+        assert!(matches!(qr.ec_level, EcLevel::Q), "Expected EcLevel::Q");
     }
 
     #[test]
@@ -679,7 +721,7 @@ mod tests {
             (6, 6),
         ];
         for &(x, y) in &expected_pattern {
-            assert_eq!(qr.get(x, y), Some(One(true)));
+            assert_eq!(qr.get(x, y), Some(One(ModuleRole::Finder)));
         }
     }
 
@@ -710,4 +752,35 @@ mod tests {
         assert!(matches!(qr.get(0, 0), Some(One(_))));
         assert!(matches!(qr.get(176, 176), Some(Zero(_))));
     }
+
+    #[test]
+    fn functional_patterns_are_tagged_with_their_role() {
+        let mut qr = QrCode::new(7, EcLevel::M, MaskPattern::Fields, Encoding::Byte).unwrap();
+        qr.all_functional_patterns();
+        assert_eq!(qr.role(3, 3), Some(ModuleRole::Finder));
+        assert_eq!(qr.role(7, 3), Some(ModuleRole::Separator));
+        assert_eq!(qr.role(10, 6), Some(ModuleRole::Timing));
+        assert_eq!(qr.role(22, 22), Some(ModuleRole::Alignment));
+        assert_eq!(qr.role(0, 8), Some(ModuleRole::FormatInfo));
+        assert_eq!(qr.role(0, qr.size() - 11), Some(ModuleRole::VersionInfo));
+        assert_eq!(qr.role(8, 4 * 7 + 9), Some(ModuleRole::DarkModule));
+        assert_eq!(qr.role(12, 12), Some(ModuleRole::Empty));
+        assert_eq!(qr.role(100, 100), None);
+    }
+
+    #[test]
+    fn fill_tags_codewords_and_remainder_bits() {
+        let mut qr = QrCode::new(2, EcLevel::L, MaskPattern::Fields, Encoding::Byte).unwrap();
+        qr.all_functional_patterns();
+        // version 2-L holds 34 data and 10 EC codewords, followed by 7 remainder bits
+        qr.fill(&vec![Bit::One(ModuleRole::Empty); 44 * 8]);
+
+        let roles: Vec<_> = qr.data.iter().map(|bit| bit.role()).collect();
+        let count = |f: fn(&ModuleRole) -> bool| roles.iter().filter(|role| f(role)).count();
+        assert_eq!(count(|role| matches!(role, ModuleRole::Data(_))), 34 * 8);
+        assert_eq!(count(|role| matches!(role, ModuleRole::Ec(_))), 10 * 8);
+        assert_eq!(count(|role| *role == ModuleRole::Remainder), 7);
+        assert_eq!(count(|role| *role == ModuleRole::Empty), 0);
+        assert_eq!(qr.role(24, 24), Some(ModuleRole::Data(0)));
+    }
 }
@@ -0,0 +1,246 @@
+use crate::qrcode::QrCode;
+use crate::render::text::{self, TextOptions};
+use crate::render::{dark_at, Color, RenderOptions, Renderer};
+use std::io::IsTerminal;
+
+/// How background colours are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnsiColors {
+    /// SGR 40 for dark modules and SGR 47 for light ones, the colours of the options are ignored.
+    Basic,
+    /// Closest colour of the 256 colour palette.
+    Palette256,
+    /// 24-bit colours.
+    TrueColor,
+}
+
+impl AnsiColors {
+    /// Colours supported by the terminal on stdout, `None` when `NO_COLOR` is set or stdout is
+    /// not a terminal.
+    pub fn detect() -> Option<AnsiColors> {
+        detect_with(
+            |name| std::env::var(name).ok(),
+            std::io::stdout().is_terminal(),
+        )
+    }
+
+    fn background(self, color: Color, dark: bool) -> String {
+        match self {
+            AnsiColors::Basic if dark => "\x1b[40m".to_string(),
+            AnsiColors::Basic => "\x1b[47m".to_string(),
+            AnsiColors::Palette256 => format!("\x1b[48;5;{}m", palette_index(color)),
+            AnsiColors::TrueColor => format!("\x1b[48;2;{};{};{}m", color.r, color.g, color.b),
+        }
+    }
+}
+
+fn detect_with(var: impl Fn(&str) -> Option<String>, is_terminal: bool) -> Option<AnsiColors> {
+    // https://no-color.org: any non empty value disables colours
+    if !is_terminal || var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return None;
+    }
+
+    let colorterm = var("COLORTERM").unwrap_or_default();
+    let term = var("TERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        Some(AnsiColors::TrueColor)
+    } else if term.contains("256color") {
+        Some(AnsiColors::Palette256)
+    } else {
+        Some(AnsiColors::Basic)
+    }
+}
+
+/// Index of the closest colour in the 6×6×6 cube or the grey ramp of the 256 colour palette.
+fn palette_index(color: Color) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |component: u8| {
+        (0..6)
+            .min_by_key(|&i| (LEVELS[i] as i32 - component as i32).abs())
+            .unwrap()
+    };
+    let (r, g, b) = (level(color.r), level(color.g), level(color.b));
+    let cube = Color::rgb(LEVELS[r], LEVELS[g], LEVELS[b]);
+
+    let average = (color.r as u32 + color.g as u32 + color.b as u32) / 3;
+    let grey_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey_level = 8 + 10 * grey_step;
+    let grey = Color::rgb(grey_level, grey_level, grey_level);
+
+    let distance = |other: Color| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(color.r, other.r) + d(color.g, other.g) + d(color.b, other.b)
+    };
+    if distance(grey) < distance(cube) {
+        232 + grey_step
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
+    }
+}
+
+pub struct AnsiOptions {
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub colors: AnsiColors,
+    pub dark: Color,
+    pub light: Color,
+    /// Swaps dark and light modules, for readers that expect light modules on dark.
+    pub invert: bool,
+}
+
+impl Default for AnsiOptions {
+    fn default() -> Self {
+        AnsiOptions {
+            quiet_zone: 4,
+            colors: AnsiColors::Basic,
+            dark: Color::BLACK,
+            light: Color::WHITE,
+            invert: false,
+        }
+    }
+}
+
+/// Draws every module as two spaces with an explicit background colour, so the symbol scans on
+/// light and dark themes alike. Every line is reset to the default colours before its newline.
+pub fn render(qrcode: &QrCode, options: &AnsiOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let dark_sgr = options.colors.background(options.dark, true);
+    let light_sgr = options.colors.background(options.light, false);
+    let mut text = String::new();
+
+    for y in 0..modules {
+        let mut previous = None;
+        for x in 0..modules {
+            let dark = dark_at(qrcode, options.quiet_zone, x, y) != options.invert;
+            if previous != Some(dark) {
+                text.push_str(if dark { &dark_sgr } else { &light_sgr });
+                previous = Some(dark);
+            }
+            text.push_str("  ");
+        }
+        text.push_str("\x1b[0m\n");
+    }
+
+    text
+}
+
+/// Renders with the colours stdout supports, or with `▀▄█` half blocks when `NO_COLOR` is set or
+/// stdout is not a terminal. The half blocks then assume light text on a dark background.
+pub fn render_for_stdout(qrcode: &QrCode, options: &AnsiOptions) -> String {
+    match AnsiColors::detect() {
+        Some(colors) => render(qrcode, &AnsiOptions { colors, ..*options }),
+        None => text::half_blocks(
+            qrcode,
+            &TextOptions {
+                quiet_zone: options.quiet_zone,
+                invert: !options.invert,
+            },
+        ),
+    }
+}
+
+/// `Renderer` for ANSI colours, the scale is ignored.
+pub struct Ansi {
+    pub colors: AnsiColors,
+}
+
+impl Default for Ansi {
+    fn default() -> Self {
+        Ansi {
+            colors: AnsiColors::Basic,
+        }
+    }
+}
+
+impl Renderer for Ansi {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        let ansi_options = AnsiOptions {
+            quiet_zone: options.quiet_zone,
+            colors: self.colors,
+            dark: options.dark,
+            light: options.light,
+            invert: options.invert,
+        };
+        render(qrcode, &ansi_options)
+    }
+}
+
+impl QrCode {
+    pub fn to_ansi(&self, options: &AnsiOptions) -> String {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn render_sets_background_on_colour_changes() {
+        let options = AnsiOptions {
+            quiet_zone: 1,
+            ..Default::default()
+        };
+        let text = render(&test_qrcode(), &options);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 23);
+        assert_eq!(lines[0], format!("\x1b[47m{}\x1b[0m", " ".repeat(46)));
+        // quiet zone, then the 7 dark modules of the finder pattern and its separator
+        assert!(lines[1].starts_with("\x1b[47m  \x1b[40m              \x1b[47m  "));
+
+        let inverted = render(
+            &test_qrcode(),
+            &AnsiOptions {
+                invert: true,
+                ..options
+            },
+        );
+        assert!(inverted.starts_with("\x1b[40m  "));
+    }
+
+    #[test]
+    fn render_writes_palette_and_true_colours() {
+        let options = AnsiOptions {
+            colors: AnsiColors::TrueColor,
+            dark: Color::rgb(0, 0, 128),
+            ..Default::default()
+        };
+        let text = render(&test_qrcode(), &options);
+        assert!(text.starts_with("\x1b[48;2;255;255;255m"));
+        assert!(text.contains("\x1b[48;2;0;0;128m"));
+
+        assert_eq!(palette_index(Color::BLACK), 16);
+        assert_eq!(palette_index(Color::WHITE), 231);
+        assert_eq!(palette_index(Color::rgb(0, 0, 128)), 18);
+        assert_eq!(palette_index(Color::rgb(128, 128, 128)), 244);
+    }
+
+    #[test]
+    fn detect_honours_no_color_and_terminals() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(detect_with(env(&[]), false), None);
+        assert_eq!(detect_with(env(&[("NO_COLOR", "1")]), true), None);
+        assert_eq!(
+            detect_with(env(&[("NO_COLOR", "")]), true),
+            Some(AnsiColors::Basic)
+        );
+        assert_eq!(
+            detect_with(env(&[("TERM", "xterm-256color")]), true),
+            Some(AnsiColors::Palette256)
+        );
+        assert_eq!(
+            detect_with(env(&[("COLORTERM", "truecolor")]), true),
+            Some(AnsiColors::TrueColor)
+        );
+    }
+}
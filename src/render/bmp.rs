@@ -0,0 +1,315 @@
+use crate::decode::{decode_luma, luma, Decoded};
+use crate::qrcode::QrCode;
+use crate::render::{dark_at, Color};
+
+const FILE_HEADER_SIZE: u32 = 14;
+const INFO_HEADER_SIZE: u32 = 40;
+/// 72 DPI in pixels per metre.
+const PIXELS_PER_METRE: u32 = 2835;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BmpDepth {
+    /// Two colour palette.
+    One,
+    /// BGR pixels.
+    TwentyFour,
+}
+
+pub struct BmpOptions {
+    pub depth: BmpDepth,
+    /// Size of a module in pixels.
+    pub scale: u32,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Default for BmpOptions {
+    fn default() -> Self {
+        BmpOptions {
+            depth: BmpDepth::One,
+            scale: 8,
+            quiet_zone: 4,
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+        }
+    }
+}
+
+/// Renders the symbol as an uncompressed bottom-up BMP image.
+pub fn render(qrcode: &QrCode, options: &BmpOptions) -> Vec<u8> {
+    let scale = options.scale.max(1);
+    let width = (qrcode.size() + 2 * options.quiet_zone) * scale;
+    let dark = |x: u32, y: u32| dark_at(qrcode, options.quiet_zone, x / scale, y / scale);
+    let (bits, palette_size) = match options.depth {
+        BmpDepth::One => (1, 2),
+        BmpDepth::TwentyFour => (24, 0),
+    };
+    // rows are padded to 4 bytes
+    let row_bytes = (width * bits).div_ceil(32) * 4;
+    let offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE + 4 * palette_size;
+    let image_size = row_bytes * width;
+
+    let mut bmp = Vec::with_capacity((offset + image_size) as usize);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(offset + image_size).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&offset.to_le_bytes());
+
+    bmp.extend_from_slice(&INFO_HEADER_SIZE.to_le_bytes());
+    bmp.extend_from_slice(&(width as i32).to_le_bytes());
+    bmp.extend_from_slice(&(width as i32).to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&(bits as u16).to_le_bytes());
+    // no compression
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&image_size.to_le_bytes());
+    bmp.extend_from_slice(&PIXELS_PER_METRE.to_le_bytes());
+    bmp.extend_from_slice(&PIXELS_PER_METRE.to_le_bytes());
+    bmp.extend_from_slice(&palette_size.to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+
+    let bgr = |color: Color| [color.b, color.g, color.r];
+    if options.depth == BmpDepth::One {
+        // the background is entry 0 and the foreground entry 1
+        for color in [options.background, options.foreground] {
+            bmp.extend_from_slice(&bgr(color));
+            bmp.push(0);
+        }
+    }
+
+    for y in (0..width).rev() {
+        let mut row = vec![0u8; row_bytes as usize];
+        for x in 0..width {
+            let dark = dark(x, y);
+            match options.depth {
+                BmpDepth::One if dark => row[(x / 8) as usize] |= 0x80 >> (x % 8),
+                BmpDepth::One => {}
+                BmpDepth::TwentyFour => {
+                    let color = if dark {
+                        options.foreground
+                    } else {
+                        options.background
+                    };
+                    let start = (x * 3) as usize;
+                    row[start..start + 3].copy_from_slice(&bgr(color));
+                }
+            }
+        }
+        bmp.extend_from_slice(&row);
+    }
+
+    bmp
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, String> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or("BMP is truncated.".to_string())
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or("BMP is truncated.".to_string())
+}
+
+/// Reads an uncompressed BMP image of 1, 4, 8, 24 or 32 bits per pixel as 8-bit luma, returns
+/// `(width, height, pixels)`.
+pub fn read(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    if !data.starts_with(b"BM") {
+        return Err("Not a BMP image.".to_string());
+    }
+    let offset = u32_at(data, 10)? as usize;
+    let info_size = u32_at(data, 14)?;
+    if info_size < INFO_HEADER_SIZE {
+        return Err("BMP core headers are not supported.".to_string());
+    }
+    let width = u32_at(data, 18)? as i32;
+    let height = u32_at(data, 22)? as i32;
+    let bits = u16_at(data, 28)? as u32;
+    if u32_at(data, 30)? != 0 {
+        return Err("Compressed BMP images are not supported.".to_string());
+    }
+    if width <= 0 || height == 0 {
+        return Err("BMP size is invalid.".to_string());
+    }
+    // a negative height means rows are stored top-down
+    let (width, top_down, height) = (width as u32, height < 0, height.unsigned_abs());
+
+    let palette: Vec<u8> = if bits <= 8 {
+        let colors = match u32_at(data, 46)? {
+            0 => 1 << bits,
+            count => count,
+        };
+        let start = (FILE_HEADER_SIZE + info_size) as usize;
+        (0..colors as usize)
+            .map(|i| {
+                let entry = data
+                    .get(start + 4 * i..start + 4 * i + 3)
+                    .ok_or("BMP palette is truncated.")?;
+                Ok(luma(entry[2], entry[1], entry[0]))
+            })
+            .collect::<Result<_, String>>()?
+    } else {
+        Vec::new()
+    };
+
+    let too_large = || "BMP size is too large.".to_string();
+    let row_bytes = width
+        .checked_mul(bits)
+        .map(|row_bits| row_bits.div_ceil(32) as usize * 4)
+        .ok_or_else(too_large)?;
+    let raster_bytes = row_bytes
+        .checked_mul(height as usize)
+        .ok_or_else(too_large)?;
+    if data.len().saturating_sub(offset) < raster_bytes {
+        return Err("BMP raster is truncated.".to_string());
+    }
+    let count = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(too_large)?;
+    let mut pixels = Vec::with_capacity(count);
+    for y in 0..height {
+        let stored = if top_down { y } else { height - 1 - y };
+        let start = offset + stored as usize * row_bytes;
+        let row = data
+            .get(start..start + row_bytes)
+            .ok_or("BMP raster is truncated.")?;
+        for x in 0..width as usize {
+            let luma = match bits {
+                1 | 4 | 8 => {
+                    let bit = x * bits as usize;
+                    let index = (row[bit / 8] >> (8 - bits as usize - bit % 8)) & ((1 << bits) - 1);
+                    *palette
+                        .get(index as usize)
+                        .ok_or("BMP palette index is out of range.")?
+                }
+                24 | 32 => {
+                    let pixel = &row[x * bits as usize / 8..];
+                    luma(pixel[2], pixel[1], pixel[0])
+                }
+                _ => return Err(format!("{} bits per pixel is not supported.", bits)),
+            };
+            pixels.push(luma);
+        }
+    }
+
+    Ok((width, height, pixels))
+}
+
+/// Decodes a symbol from a BMP image.
+pub fn decode(data: &[u8]) -> Result<Decoded, String> {
+    let (width, height, pixels) = read(data)?;
+    decode_luma(width, height, &pixels)
+}
+
+impl QrCode {
+    pub fn to_bmp(&self, options: &BmpOptions) -> Vec<u8> {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn render_writes_headers_and_padded_rows() {
+        let options = BmpOptions {
+            scale: 1,
+            quiet_zone: 0,
+            ..Default::default()
+        };
+        let bmp = test_qrcode().to_bmp(&options);
+        assert_eq!(&bmp[..2], b"BM");
+        // 21 pixels of 1 bit are padded to 4 bytes per row, after a palette of 2 entries
+        assert_eq!(u32_at(&bmp, 10), Ok(62));
+        assert_eq!(bmp.len(), 62 + 21 * 4);
+        assert_eq!(u32_at(&bmp, 2), Ok(bmp.len() as u32));
+        assert_eq!(u16_at(&bmp, 28), Ok(1));
+        // the last row is the bottom of the bottom left finder pattern
+        assert_eq!(bmp[62], 0b1111_1110);
+
+        let bmp = test_qrcode().to_bmp(&BmpOptions {
+            depth: BmpDepth::TwentyFour,
+            ..options
+        });
+        // 63 bytes of pixels padded to 64
+        assert_eq!(bmp.len(), 54 + 21 * 64);
+    }
+
+    #[test]
+    fn both_depths_decode() {
+        for depth in [BmpDepth::One, BmpDepth::TwentyFour] {
+            let options = BmpOptions {
+                depth,
+                scale: 3,
+                foreground: Color::rgb(0x20, 0x40, 0x60),
+                ..Default::default()
+            };
+            let bmp = test_qrcode().to_bmp(&options);
+            let (width, height, _) = read(&bmp).unwrap();
+            assert_eq!((width, height), (87, 87));
+            assert_eq!(decode(&bmp).unwrap().data, b"HELLO WORLD");
+        }
+    }
+
+    #[test]
+    fn read_handles_top_down_rows() {
+        let mut bmp = test_qrcode().to_bmp(&BmpOptions {
+            depth: BmpDepth::TwentyFour,
+            scale: 1,
+            quiet_zone: 0,
+            ..Default::default()
+        });
+        let (_, _, bottom_up) = read(&bmp).unwrap();
+
+        // flip the rows and negate the height
+        let rows: Vec<Vec<u8>> = bmp[54..].chunks(64).map(<[u8]>::to_vec).collect();
+        bmp.truncate(54);
+        rows.iter().rev().for_each(|row| bmp.extend_from_slice(row));
+        bmp[22..26].copy_from_slice(&(-21i32).to_le_bytes());
+
+        assert_eq!(read(&bmp).unwrap().2, bottom_up);
+        assert!(read(b"BM").is_err());
+    }
+
+    #[test]
+    fn read_rejects_oversized_headers() {
+        let header = |width: i32, height: i32, bits: u16| {
+            let mut bmp = test_qrcode().to_bmp(&BmpOptions {
+                depth: BmpDepth::TwentyFour,
+                ..Default::default()
+            });
+            bmp.truncate(54);
+            bmp[18..22].copy_from_slice(&width.to_le_bytes());
+            bmp[22..26].copy_from_slice(&height.to_le_bytes());
+            bmp[28..30].copy_from_slice(&bits.to_le_bytes());
+            bmp
+        };
+        assert_eq!(
+            read(&header(70000, 70000, 24)),
+            Err("BMP raster is truncated.".to_string())
+        );
+        assert_eq!(
+            read(&header(i32::MAX, 1, 32)),
+            Err("BMP size is too large.".to_string())
+        );
+    }
+
+    #[test]
+    fn render_clamps_scale_to_one() {
+        let zero = test_qrcode().to_bmp(&BmpOptions {
+            scale: 0,
+            ..Default::default()
+        });
+        let one = test_qrcode().to_bmp(&BmpOptions {
+            scale: 1,
+            ..Default::default()
+        });
+        assert_eq!(zero, one);
+    }
+}
@@ -0,0 +1,532 @@
+use crate::qrcode::QrCode;
+use crate::render::png::encode_rgba;
+use crate::render::style::Outline;
+use crate::render::svg::{escape, push_outline};
+use crate::render::{dark_at, dark_runs, number, Color, RenderOptions, Renderer};
+use std::f64::consts::SQRT_2;
+use std::fmt::Write;
+
+/// Height of the caption band under the quiet zone, in modules.
+const BAND_HEIGHT: u32 = 4;
+
+/// 5x7 glyphs for ASCII 0x20 to 0x7E, a byte per column with the top row in the lowest bit.
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x5F, 0x00, 0x00],
+    [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14],
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12],
+    [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50],
+    [0x00, 0x05, 0x03, 0x00, 0x00],
+    [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00],
+    [0x14, 0x08, 0x3E, 0x08, 0x14],
+    [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00],
+    [0x08, 0x08, 0x08, 0x08, 0x08],
+    [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02],
+    [0x3E, 0x51, 0x49, 0x45, 0x3E],
+    [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46],
+    [0x21, 0x41, 0x45, 0x4B, 0x31],
+    [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39],
+    [0x3C, 0x4A, 0x49, 0x49, 0x30],
+    [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36],
+    [0x06, 0x49, 0x49, 0x29, 0x1E],
+    [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00],
+    [0x08, 0x14, 0x22, 0x41, 0x00],
+    [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08],
+    [0x02, 0x01, 0x51, 0x09, 0x06],
+    [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E],
+    [0x7F, 0x49, 0x49, 0x49, 0x36],
+    [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C],
+    [0x7F, 0x49, 0x49, 0x49, 0x41],
+    [0x7F, 0x09, 0x09, 0x09, 0x01],
+    [0x3E, 0x41, 0x49, 0x49, 0x7A],
+    [0x7F, 0x08, 0x08, 0x08, 0x7F],
+    [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01],
+    [0x7F, 0x08, 0x14, 0x22, 0x41],
+    [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F],
+    [0x7F, 0x04, 0x08, 0x10, 0x7F],
+    [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06],
+    [0x3E, 0x41, 0x51, 0x21, 0x5E],
+    [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31],
+    [0x01, 0x01, 0x7F, 0x01, 0x01],
+    [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F],
+    [0x3F, 0x40, 0x38, 0x40, 0x3F],
+    [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07],
+    [0x61, 0x51, 0x49, 0x45, 0x43],
+    [0x00, 0x7F, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20],
+    [0x00, 0x41, 0x41, 0x7F, 0x00],
+    [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0x00, 0x01, 0x02, 0x04, 0x00],
+    [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38],
+    [0x38, 0x44, 0x44, 0x44, 0x20],
+    [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18],
+    [0x08, 0x7E, 0x09, 0x01, 0x02],
+    [0x0C, 0x52, 0x52, 0x52, 0x3E],
+    [0x7F, 0x08, 0x04, 0x04, 0x78],
+    [0x00, 0x44, 0x7D, 0x40, 0x00],
+    [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x7F, 0x10, 0x28, 0x44, 0x00],
+    [0x00, 0x41, 0x7F, 0x40, 0x00],
+    [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78],
+    [0x38, 0x44, 0x44, 0x44, 0x38],
+    [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C],
+    [0x7C, 0x08, 0x04, 0x04, 0x08],
+    [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20],
+    [0x3C, 0x40, 0x40, 0x20, 0x7C],
+    [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C],
+    [0x44, 0x28, 0x10, 0x28, 0x44],
+    [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44],
+    [0x00, 0x08, 0x36, 0x41, 0x00],
+    [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00],
+    [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+/// Glyph of a character, characters outside printable ASCII are drawn as `?`.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        ' '..='~' => FONT[c as usize - 0x20],
+        _ => FONT['?' as usize - 0x20],
+    }
+}
+
+/// Border drawn around the symbol, its quiet zone and caption.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    /// Stroke width in modules.
+    pub width: u32,
+    /// Radius of the outer corners in modules. It is limited to about 3.4 times the width, so
+    /// that the rounded corners never cut into the square inside edge of the frame.
+    pub radius: f64,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Frame {
+            width: 1,
+            radius: 2.0,
+        }
+    }
+}
+
+/// Text under the quiet zone, with an optional frame and call to action banner.
+pub struct Caption {
+    /// Caption text, nothing is added under the symbol when it is empty and there is no banner.
+    pub text: String,
+    pub frame: Option<Frame>,
+    /// Fills the caption band with the dark colour and draws the text with the light one.
+    pub banner: bool,
+}
+
+impl Default for Caption {
+    fn default() -> Self {
+        Caption {
+            text: "Scan me".to_string(),
+            frame: None,
+            banner: false,
+        }
+    }
+}
+
+/// Position of every part of the picture, in modules from its top left corner.
+struct Layout {
+    width: u32,
+    height: u32,
+    /// Top left corner of the quiet zone.
+    symbol: u32,
+    /// Side of the symbol with its quiet zone.
+    side: u32,
+    /// Edge of the picture, rounded when framed.
+    outer: Outline,
+    /// Inside edge of the frame, square so that the quiet zone corners stay light.
+    inner: Outline,
+    /// Caption band under the quiet zone.
+    band: Option<Outline>,
+}
+
+impl Caption {
+    fn layout(&self, qrcode: &QrCode, quiet_zone: u32) -> Layout {
+        let side = qrcode.size() + 2 * quiet_zone;
+        let (border, radius) = self
+            .frame
+            .map_or((0, 0.0), |frame| (frame.width, frame.radius));
+        let band_height = if self.text.is_empty() && !self.banner {
+            0
+        } else {
+            BAND_HEIGHT
+        };
+        let (width, height) = (side + 2 * border, side + band_height + 2 * border);
+        // the outer arc passes through the inner corner at a radius of (2 + √2) times the border
+        let radius = radius
+            .min(border as f64 * (2.0 + SQRT_2))
+            .min(width.min(height) as f64 / 2.0)
+            .max(0.0);
+        let rectangle = |left: u32, top: u32, right: u32, bottom: u32, radii| Outline {
+            left: left as f64,
+            top: top as f64,
+            right: right as f64,
+            bottom: bottom as f64,
+            radii,
+        };
+
+        Layout {
+            width,
+            height,
+            symbol: border,
+            side,
+            outer: rectangle(0, 0, width, height, [radius; 4]),
+            inner: rectangle(border, border, width - border, height - border, [0.0; 4]),
+            band: (band_height > 0).then(|| {
+                let top = border + side;
+                rectangle(border, top, border + side, top + band_height, [0.0; 4])
+            }),
+        }
+    }
+
+    /// Colours of the text and of the caption band.
+    fn text_colors(&self, options: &RenderOptions) -> (Color, Color) {
+        let (dark, light) = options.colors();
+        if self.banner {
+            (light, dark)
+        } else {
+            (dark, light)
+        }
+    }
+}
+
+/// RGBA pixels of the captioned symbol, `options.scale` pixels per module, as
+/// `(width, height, pixels)`. Pixels outside the rounded corners of the frame are transparent.
+pub fn rgba_pixels(
+    qrcode: &QrCode,
+    options: &RenderOptions,
+    caption: &Caption,
+) -> Result<(u32, u32, Vec<u8>), String> {
+    let scale = options.scale.max(1);
+    let layout = caption.layout(qrcode, options.quiet_zone);
+    let (width, height) = (layout.width * scale, layout.height * scale);
+    let (dark, light) = options.colors();
+    let (text_color, band_color) = caption.text_colors(options);
+
+    // text dots, centred in the band and as large as fits in half its height
+    let chars: Vec<char> = caption.text.chars().collect();
+    let text_width = (6 * chars.len() as u32).saturating_sub(1);
+    let mut dot = 0;
+    let mut origin = (0, 0);
+    if let (Some(band), true) = (layout.band, text_width > 0) {
+        let (band_width, band_height) = (layout.side * scale, BAND_HEIGHT * scale);
+        // leave a module on each side
+        let room = (layout.side.saturating_sub(2) * scale) / text_width;
+        dot = (band_height / 2 / 7).min(room);
+        if dot == 0 {
+            return Err(format!(
+                "Caption of {} characters doesn't fit under the symbol.",
+                chars.len()
+            ));
+        }
+        origin = (
+            band.left as u32 * scale + (band_width - text_width * dot) / 2,
+            band.top as u32 * scale + (band_height - 7 * dot) / 2,
+        );
+    }
+    let text_at = |px: u32, py: u32| {
+        if dot == 0 || px < origin.0 || py < origin.1 {
+            return false;
+        }
+        let (column, row) = ((px - origin.0) / dot, (py - origin.1) / dot);
+        let (index, column) = ((column / 6) as usize, column % 6);
+        row < 7
+            && column < 5
+            && index < chars.len()
+            && glyph(chars[index])[column as usize] >> row & 1 == 1
+    };
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for py in 0..height {
+        for px in 0..width {
+            let (mx, my) = (px / scale, py / scale);
+            let (fx, fy) = (
+                (px as f64 + 0.5) / scale as f64,
+                (py as f64 + 0.5) / scale as f64,
+            );
+            let in_symbol = (layout.symbol..layout.symbol + layout.side).contains(&mx)
+                && (layout.symbol..layout.symbol + layout.side).contains(&my);
+            let color = if !layout.outer.contains(fx, fy) {
+                None
+            } else if !layout.inner.contains(fx, fy) {
+                Some(dark)
+            } else if in_symbol {
+                let is_dark = dark_at(
+                    qrcode,
+                    options.quiet_zone,
+                    mx - layout.symbol,
+                    my - layout.symbol,
+                );
+                Some(if is_dark { dark } else { light })
+            } else if text_at(px, py) {
+                Some(text_color)
+            } else if layout.band.is_some_and(|band| band.contains(fx, fy)) {
+                Some(band_color)
+            } else {
+                Some(light)
+            };
+            match color {
+                Some(color) => pixels.extend_from_slice(&[color.r, color.g, color.b, 255]),
+                None => pixels.extend_from_slice(&[0; 4]),
+            }
+        }
+    }
+
+    Ok((width, height, pixels))
+}
+
+/// Renders the captioned symbol as an RGBA PNG image.
+pub fn render_png(
+    qrcode: &QrCode,
+    options: &RenderOptions,
+    caption: &Caption,
+) -> Result<Vec<u8>, String> {
+    let (width, height, pixels) = rgba_pixels(qrcode, options, caption)?;
+    Ok(encode_rgba(width, height, &pixels))
+}
+
+/// Renders the captioned symbol as an SVG document, the caption is a `<text>` element in the
+/// viewer's sans-serif font, squeezed when it is wider than the symbol.
+pub fn render_svg(qrcode: &QrCode, options: &RenderOptions, caption: &Caption) -> String {
+    let layout = caption.layout(qrcode, options.quiet_zone);
+    let (dark, light) = options.colors();
+    let (text_color, band_color) = caption.text_colors(options);
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\">",
+        layout.width * options.scale,
+        layout.height * options.scale,
+        layout.width,
+        layout.height
+    );
+
+    let _ = write!(svg, "<path fill=\"{}\" d=\"", light.to_hex());
+    push_outline(&mut svg, &layout.outer, 0.0);
+    svg.push_str("\"/>");
+    if caption.frame.is_some() {
+        let _ = write!(
+            svg,
+            "<path fill=\"{}\" fill-rule=\"evenodd\" d=\"",
+            dark.to_hex()
+        );
+        push_outline(&mut svg, &layout.outer, 0.0);
+        push_outline(&mut svg, &layout.inner, 0.0);
+        svg.push_str("\"/>");
+    }
+
+    let offset = layout.symbol + options.quiet_zone;
+    let _ = write!(
+        svg,
+        "<path fill=\"{}\" shape-rendering=\"crispEdges\" d=\"",
+        dark.to_hex()
+    );
+    for (x, y, length) in dark_runs(qrcode) {
+        let _ = write!(
+            svg,
+            "M{} {}h{}v1h-{}z",
+            x + offset,
+            y + offset,
+            length,
+            length
+        );
+    }
+    svg.push_str("\"/>");
+
+    if let Some(band) = layout.band {
+        if caption.banner {
+            let _ = write!(svg, "<path fill=\"{}\" d=\"", band_color.to_hex());
+            push_outline(&mut svg, &band, 0.0);
+            svg.push_str("\"/>");
+        }
+        if !caption.text.is_empty() {
+            let font_size = BAND_HEIGHT as f64 / 2.0;
+            // sans-serif glyphs average about 0.6 em
+            let estimated = 0.6 * font_size * caption.text.chars().count() as f64;
+            let room = layout.side.saturating_sub(2) as f64;
+            let squeeze = if estimated > room {
+                format!(
+                    " textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\"",
+                    number(room)
+                )
+            } else {
+                String::new()
+            };
+            let _ = write!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" \
+                 text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\"{}>{}</text>",
+                number((band.left + band.right) / 2.0),
+                number((band.top + band.bottom) / 2.0),
+                number(font_size),
+                text_color.to_hex(),
+                squeeze,
+                escape(&caption.text)
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// `Renderer` for a captioned PNG, failing when the caption is too long for the scale.
+#[derive(Default)]
+pub struct CaptionedPng {
+    pub caption: Caption,
+}
+
+impl Renderer for CaptionedPng {
+    type Output = Result<Vec<u8>, String>;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Self::Output {
+        render_png(qrcode, options, &self.caption)
+    }
+}
+
+/// `Renderer` for a captioned SVG, the scale is the module size in pixels.
+#[derive(Default)]
+pub struct CaptionedSvg {
+    pub caption: Caption,
+}
+
+impl Renderer for CaptionedSvg {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        render_svg(qrcode, options, &self.caption)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::decode_rgba;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn caption_stays_out_of_the_quiet_zone() {
+        let options = RenderOptions {
+            scale: 4,
+            ..Default::default()
+        };
+        let (width, height, pixels) =
+            rgba_pixels(&test_qrcode(), &options, &Caption::default()).unwrap();
+        // 29 modules and a band of 4
+        assert_eq!((width, height), (116, 132));
+
+        // the symbol with its quiet zone decodes on its own
+        let symbol: Vec<u8> = pixels[..(width * width * 4) as usize].to_vec();
+        assert_eq!(
+            decode_rgba(width, width, &symbol).unwrap().data,
+            b"HELLO WORLD"
+        );
+        let band = &pixels[(width * width * 4) as usize..];
+        assert!(band.chunks(4).any(|pixel| pixel == [0, 0, 0, 255]));
+
+        let long = Caption {
+            text: "x".repeat(50),
+            ..Default::default()
+        };
+        assert!(render_png(&test_qrcode(), &options, &long).is_err());
+    }
+
+    #[test]
+    fn frame_and_banner_use_the_dark_colour() {
+        let caption = Caption {
+            frame: Some(Frame::default()),
+            banner: true,
+            ..Default::default()
+        };
+        let (width, height, pixels) =
+            rgba_pixels(&test_qrcode(), &RenderOptions::default(), &caption).unwrap();
+        assert_eq!((width, height), (31 * 8, 35 * 8));
+        let pixel = |x: u32, y: u32| &pixels[((y * width + x) * 4) as usize..][..4];
+        // rounded outer corner, straight frame edge, quiet zone and banner
+        assert_eq!(pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(0, 100), [0, 0, 0, 255]);
+        assert_eq!(pixel(10, 10), [255, 255, 255, 255]);
+        assert_eq!(pixel(10, 31 * 8 + 2), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn large_frame_radius_keeps_the_quiet_zone_light() {
+        let caption = Caption {
+            text: String::new(),
+            frame: Some(Frame {
+                width: 1,
+                radius: 20.0,
+            }),
+            banner: false,
+        };
+        let (width, _, pixels) =
+            rgba_pixels(&test_qrcode(), &RenderOptions::default(), &caption).unwrap();
+        let pixel = |x: u32, y: u32| &pixels[((y * width + x) * 4) as usize..][..4];
+        // every pixel of the quiet zone, inside the frame of 8 pixels
+        for y in 8..width - 8 {
+            for x in 8..width - 8 {
+                let quiet = |v: u32| !(8 + 4 * 8..width - 8 - 4 * 8).contains(&v);
+                if quiet(x) || quiet(y) {
+                    assert_eq!(pixel(x, y), [255, 255, 255, 255], "({}, {})", x, y);
+                }
+            }
+        }
+        assert_eq!(pixel(0, 0), [0, 0, 0, 0]);
+
+        let svg = test_qrcode().render_with(CaptionedSvg { caption }).build();
+        assert!(svg.contains("A3.414 3.414 0 0 1 31 3.414"));
+        assert!(svg.contains("zM1 1H30V30H1V1z\"/>"));
+    }
+
+    #[test]
+    fn svg_caption_is_escaped_text() {
+        let caption = Caption {
+            text: "Scan & pay".to_string(),
+            frame: Some(Frame::default()),
+            banner: true,
+        };
+        let svg = test_qrcode()
+            .render_with(CaptionedSvg { caption })
+            .scale(2)
+            .build();
+        assert!(svg.contains("width=\"62\" height=\"70\" viewBox=\"0 0 31 35\""));
+        assert!(svg.contains("fill-rule=\"evenodd\" d=\"M2 0H29A2 2 0 0 1 31 2"));
+        assert!(svg.contains(
+            "<text x=\"15.5\" y=\"32\" font-family=\"sans-serif\" font-size=\"2\" \
+             text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#ffffff\">Scan &amp; \
+             pay</text>"
+        ));
+    }
+}
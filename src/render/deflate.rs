@@ -0,0 +1,220 @@
+// Minimal zlib stream writer: LZ77 with fixed Huffman codes, enough to keep rendered images
+// small without depending on a compression crate.
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+const MAX_CHAIN: usize = 32;
+
+/// Base length and extra bits of length codes 257 to 285.
+#[rustfmt::skip]
+const LENGTHS: [(u16, u8); 29] = [
+    (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0), (11, 1), (13, 1), (15, 1),
+    (17, 1), (19, 2), (23, 2), (27, 2), (31, 2), (35, 3), (43, 3), (51, 3), (59, 3), (67, 4),
+    (83, 4), (99, 4), (115, 4), (131, 5), (163, 5), (195, 5), (227, 5), (258, 0),
+];
+
+/// Base distance and extra bits of distance codes 0 to 29.
+#[rustfmt::skip]
+const DISTANCES: [(u16, u8); 30] = [
+    (1, 0), (2, 0), (3, 0), (4, 0), (5, 1), (7, 1), (9, 2), (13, 2), (17, 3), (25, 3), (33, 4),
+    (49, 4), (65, 5), (97, 5), (129, 6), (193, 6), (257, 7), (385, 7), (513, 8), (769, 8),
+    (1025, 9), (1537, 9), (2049, 10), (3073, 10), (4097, 11), (6145, 11), (8193, 12), (12289, 12),
+    (16385, 13), (24577, 13),
+];
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn new(bytes: Vec<u8>) -> BitWriter {
+        BitWriter {
+            bytes,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    /// Writes the `n_bits` lowest bits of `value`, least significant bit first.
+    fn write(&mut self, value: u32, n_bits: u8) {
+        self.buffer |= value << self.count;
+        self.count += n_bits;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which is stored most significant bit first.
+    fn write_code(&mut self, code: u32, n_bits: u8) {
+        self.write(code.reverse_bits() >> (32 - n_bits), n_bits);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+
+    fn literal(&mut self, value: u16) {
+        match value {
+            0..=143 => self.write_code(0x30 + value as u32, 8),
+            144..=255 => self.write_code(0x190 + (value - 144) as u32, 9),
+            256..=279 => self.write_code((value - 256) as u32, 7),
+            _ => self.write_code(0xC0 + (value - 280) as u32, 8),
+        }
+    }
+
+    fn back_reference(&mut self, length: usize, distance: usize) {
+        let code = LENGTHS
+            .iter()
+            .rposition(|&(base, _)| base as usize <= length)
+            .unwrap();
+        let (base, extra) = LENGTHS[code];
+        self.literal(257 + code as u16);
+        self.write((length - base as usize) as u32, extra);
+
+        let code = DISTANCES
+            .iter()
+            .rposition(|&(base, _)| base as usize <= distance)
+            .unwrap();
+        let (base, extra) = DISTANCES[code];
+        self.write_code(code as u32, 5);
+        self.write((distance - base as usize) as u32, extra);
+    }
+}
+
+/// Hash chains of the positions already seen, to look for earlier repetitions.
+struct Matcher<'a> {
+    data: &'a [u8],
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl Matcher<'_> {
+    fn hash(&self, position: usize) -> Option<usize> {
+        let bytes = self.data.get(position..position + MIN_MATCH)?;
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        Some((value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize)
+    }
+
+    fn insert(&mut self, position: usize) {
+        if let Some(hash) = self.hash(position) {
+            self.previous[position] = self.head[hash];
+            self.head[hash] = position;
+        }
+    }
+
+    /// Longest earlier repetition of the bytes at `position`, as `(length, distance)`.
+    fn longest_match(&self, position: usize) -> (usize, usize) {
+        let mut best = (0, 0);
+        let Some(hash) = self.hash(position) else {
+            return best;
+        };
+
+        let max_length = MAX_MATCH.min(self.data.len() - position);
+        let mut candidate = self.head[hash];
+        for _ in 0..MAX_CHAIN {
+            if candidate == usize::MAX || position - candidate > WINDOW_SIZE {
+                break;
+            }
+            let length = self.data[candidate..]
+                .iter()
+                .zip(&self.data[position..position + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best.0 {
+                best = (length, position - candidate);
+                if length == max_length {
+                    break;
+                }
+            }
+            candidate = self.previous[candidate];
+        }
+        best
+    }
+}
+
+/// Compresses `data` in a single fixed Huffman block, appended to `out`.
+pub(crate) fn deflate(data: &[u8], out: Vec<u8>) -> Vec<u8> {
+    let mut writer = BitWriter::new(out);
+    // final block, fixed Huffman codes
+    writer.write(1, 1);
+    writer.write(1, 2);
+
+    let mut matcher = Matcher {
+        data,
+        head: vec![usize::MAX; 1 << HASH_BITS],
+        previous: vec![usize::MAX; data.len()],
+    };
+
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = matcher.longest_match(position);
+        if length >= MIN_MATCH {
+            writer.back_reference(length, distance);
+            for p in position..position + length {
+                matcher.insert(p);
+            }
+            position += length;
+        } else {
+            writer.literal(data[position] as u16);
+            matcher.insert(position);
+            position += 1;
+        }
+    }
+
+    // end of block
+    writer.literal(256);
+    writer.finish()
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+/// Wraps the deflated `data` in a zlib stream.
+pub(crate) fn zlib(data: &[u8]) -> Vec<u8> {
+    // 32K window, deflate, no preset dictionary, fastest compression
+    let mut out = deflate(data, vec![0x78, 0x01]);
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adler32_works() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn deflate_writes_literals_with_fixed_codes() {
+        // same bytes as zlib produces for "a" with fixed Huffman codes
+        assert_eq!(deflate(b"a", vec![]), vec![0x4B, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn deflate_compresses_repetitions() {
+        let data = vec![0u8; 10_000];
+        assert!(deflate(&data, vec![]).len() < 100);
+    }
+}
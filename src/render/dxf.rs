@@ -0,0 +1,238 @@
+use crate::qrcode::QrCode;
+use crate::render::{dark_at, number};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+pub struct DxfOptions {
+    /// Size of a module in millimetres.
+    pub module_size: f64,
+    /// Width of the quiet zone, in modules. It offsets the symbol from the origin, and is part of
+    /// the light area when `light` is set.
+    pub quiet_zone: u32,
+    pub layer: String,
+    /// Outlines the light modules instead of the dark ones, for inverted engraving.
+    pub light: bool,
+}
+
+impl Default for DxfOptions {
+    fn default() -> Self {
+        DxfOptions {
+            module_size: 1.0,
+            quiet_zone: 4,
+            layer: "QRCODE".to_string(),
+            light: false,
+        }
+    }
+}
+
+/// Outlines of the union of the filled cells of a `width` by `height` grid, as loops of corners
+/// with y growing downwards. Outer edges run clockwise and holes anticlockwise, and cells touching
+/// only at a corner get separate outlines.
+pub(crate) fn outlines(
+    width: u32,
+    height: u32,
+    filled: impl Fn(u32, u32) -> bool,
+) -> Vec<Vec<(u32, u32)>> {
+    // directed edges of every cell, an edge shared by two cells comes in both directions
+    let mut edges: BTreeMap<(u32, u32), Vec<(u32, u32)>> = BTreeMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !filled(x, y) {
+                continue;
+            }
+            let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+            for i in 0..4 {
+                let (start, end) = (corners[i], corners[(i + 1) % 4]);
+                let reverse = edges.get_mut(&end).and_then(|ends| {
+                    let position = ends.iter().position(|&e| e == start)?;
+                    Some(ends.swap_remove(position))
+                });
+                if reverse.is_none() {
+                    edges.entry(start).or_default().push(end);
+                }
+            }
+        }
+    }
+    // corners without edges left are removed, so the first entry always starts a new loop
+    edges.retain(|_, ends| !ends.is_empty());
+
+    let direction = |from: (u32, u32), to: (u32, u32)| {
+        (to.0 as i64 - from.0 as i64, to.1 as i64 - from.1 as i64)
+    };
+    let mut loops = Vec::new();
+    while let Some((&first, _)) = edges.first_key_value() {
+        let mut path = vec![first];
+        let mut heading: Option<(i64, i64)> = None;
+        loop {
+            let current = path[path.len() - 1];
+            let ends = edges.get_mut(&current).expect("outlines are closed");
+            // at a corner shared by diagonal cells, turning right keeps them apart
+            let index = match heading {
+                Some((dx, dy)) if ends.len() > 1 => ends
+                    .iter()
+                    .position(|&end| direction(current, end) == (-dy, dx))
+                    .unwrap_or(0),
+                _ => 0,
+            };
+            let next = ends.swap_remove(index);
+            if ends.is_empty() {
+                edges.remove(&current);
+            }
+            if next == first {
+                break;
+            }
+            heading = Some(direction(current, next));
+            path.push(next);
+        }
+
+        // keep the corners where the outline turns
+        let count = path.len();
+        let corners = (0..count)
+            .filter(|&i| {
+                let (previous, next) = (path[(i + count - 1) % count], path[(i + 1) % count]);
+                direction(previous, path[i]) != direction(path[i], next)
+            })
+            .map(|i| path[i])
+            .collect();
+        loops.push(corners);
+    }
+    loops
+}
+
+fn pair(dxf: &mut String, code: u32, value: &str) {
+    let _ = write!(dxf, "{:>3}\n{}\n", code, value);
+}
+
+/// Renders the dark modules as an R12 ASCII DXF drawing, a closed polyline per outline with
+/// adjacent modules merged so a cutter doesn't trace the edges between them. R12 has no header
+/// variable for units, drawing units are millimetres and importers should be set to match.
+pub fn render(qrcode: &QrCode, options: &DxfOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let loops = outlines(modules, modules, |x, y| {
+        dark_at(qrcode, options.quiet_zone, x, y) != options.light
+    });
+
+    let mut dxf = String::new();
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "HEADER");
+    pair(&mut dxf, 9, "$ACADVER");
+    pair(&mut dxf, 1, "AC1009");
+    // extents of the drawing, quiet zone included
+    let extent = number(modules as f64 * options.module_size);
+    for (variable, corner) in [("$EXTMIN", "0"), ("$EXTMAX", extent.as_str())] {
+        pair(&mut dxf, 9, variable);
+        pair(&mut dxf, 10, corner);
+        pair(&mut dxf, 20, corner);
+        pair(&mut dxf, 30, "0");
+    }
+    pair(&mut dxf, 0, "ENDSEC");
+
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "TABLES");
+    pair(&mut dxf, 0, "TABLE");
+    pair(&mut dxf, 2, "LAYER");
+    pair(&mut dxf, 70, "1");
+    pair(&mut dxf, 0, "LAYER");
+    pair(&mut dxf, 2, &options.layer);
+    pair(&mut dxf, 70, "0");
+    pair(&mut dxf, 62, "7");
+    pair(&mut dxf, 6, "CONTINUOUS");
+    pair(&mut dxf, 0, "ENDTAB");
+    pair(&mut dxf, 0, "ENDSEC");
+
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "ENTITIES");
+    // DXF y grows upwards, the origin is the bottom left corner of the quiet zone
+    let point = |dxf: &mut String, x: u32, y: u32| {
+        pair(dxf, 10, &number(x as f64 * options.module_size));
+        pair(dxf, 20, &number((modules - y) as f64 * options.module_size));
+        pair(dxf, 30, "0");
+    };
+    for corners in loops {
+        pair(&mut dxf, 0, "POLYLINE");
+        pair(&mut dxf, 8, &options.layer);
+        pair(&mut dxf, 66, "1");
+        point(&mut dxf, 0, modules);
+        // closed
+        pair(&mut dxf, 70, "1");
+        for (x, y) in corners {
+            pair(&mut dxf, 0, "VERTEX");
+            pair(&mut dxf, 8, &options.layer);
+            point(&mut dxf, x, y);
+        }
+        pair(&mut dxf, 0, "SEQEND");
+        pair(&mut dxf, 8, &options.layer);
+    }
+    pair(&mut dxf, 0, "ENDSEC");
+    pair(&mut dxf, 0, "EOF");
+
+    dxf
+}
+
+impl QrCode {
+    pub fn to_dxf(&self, options: &DxfOptions) -> String {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn outlines_merge_adjacent_cells() {
+        // an L of three cells and a cell touching it only at a corner
+        let filled = [(0, 0), (1, 0), (0, 1), (1, 2)];
+        let loops = outlines(3, 3, |x, y| filled.contains(&(x, y)));
+        assert_eq!(
+            loops,
+            vec![
+                vec![(0, 0), (2, 0), (2, 1), (1, 1), (1, 2), (0, 2)],
+                vec![(1, 2), (2, 2), (2, 3), (1, 3)],
+            ]
+        );
+    }
+
+    #[test]
+    fn outlines_keep_holes() {
+        // the ring of a finder pattern
+        let loops = outlines(7, 7, |x, y| x == 0 || y == 0 || x == 6 || y == 6);
+        assert_eq!(
+            loops,
+            vec![
+                vec![(0, 0), (7, 0), (7, 7), (0, 7)],
+                vec![(1, 1), (1, 6), (6, 6), (6, 1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn render_writes_closed_polylines_on_the_layer() {
+        let dxf = test_qrcode().to_dxf(&DxfOptions {
+            module_size: 0.5,
+            ..Default::default()
+        });
+        assert!(dxf.starts_with("  0\nSECTION\n  2\nHEADER\n  9\n$ACADVER\n  1\nAC1009\n"));
+        // R12 has no $INSUNITS
+        assert!(!dxf.contains("$INSUNITS"));
+        assert!(dxf.contains("  9\n$EXTMAX\n 10\n14.5\n 20\n14.5\n 30\n0\n  0\nENDSEC\n"));
+        assert!(dxf.ends_with("  0\nENDSEC\n  0\nEOF\n"));
+        // the top left finder pattern is an outline, its hole and its centre
+        assert!(dxf.contains(
+            "  0\nVERTEX\n  8\nQRCODE\n 10\n2\n 20\n12.5\n 30\n0\n  0\nVERTEX\n  8\nQRCODE\n 10\n\
+             5.5\n 20\n12.5\n"
+        ));
+        let polylines = dxf.matches("POLYLINE").count();
+        assert_eq!(polylines, dxf.matches("SEQEND").count());
+
+        // the light area is a single outline with a hole per dark outline, or an island inside
+        // a hole
+        let light = test_qrcode().to_dxf(&DxfOptions {
+            light: true,
+            ..Default::default()
+        });
+        assert!(light.contains(" 10\n0\n 20\n29\n 30\n0\n  0\nVERTEX\n  8\nQRCODE\n 10\n29\n"));
+        assert_ne!(light.matches("POLYLINE").count(), 0);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::qrcode::QrCode;
+use crate::render::style::{linear_endpoints, radial_radius, region_shapes, Outline, Paint, Style};
+use crate::render::{dark_runs, number, Color, RenderOptions, Renderer};
+use std::fmt::Write;
+
+/// Process colour, each component in percent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cmyk {
+    pub c: u8,
+    pub m: u8,
+    pub y: u8,
+    pub k: u8,
+}
+
+impl Cmyk {
+    /// 100% K only, so dark modules don't print as rich black.
+    pub const BLACK: Cmyk = Cmyk::new(0, 0, 0, 100);
+    pub const WHITE: Cmyk = Cmyk::new(0, 0, 0, 0);
+
+    pub const fn new(c: u8, m: u8, y: u8, k: u8) -> Cmyk {
+        Cmyk { c, m, y, k }
+    }
+
+    /// Components between 0 and 1, separated by spaces.
+    fn components(self) -> String {
+        let component = |percent: u8| {
+            let value = percent.min(100) as f64 / 100.0;
+            format!("{}", value)
+        };
+        format!(
+            "{} {} {} {}",
+            component(self.c),
+            component(self.m),
+            component(self.y),
+            component(self.k)
+        )
+    }
+
+    fn to_postscript(self) -> String {
+        format!("{} setcmykcolor", self.components())
+    }
+}
+
+impl From<Color> for Cmyk {
+    /// Naive conversion without a colour profile.
+    fn from(color: Color) -> Self {
+        let max = color.r.max(color.g).max(color.b) as f64 / 255.0;
+        if max == 0.0 {
+            return Cmyk::BLACK;
+        }
+        let percent = |value: f64| (value * 100.0).round() as u8;
+        let component = |c: u8| percent((max - c as f64 / 255.0) / max);
+        Cmyk::new(
+            component(color.r),
+            component(color.g),
+            component(color.b),
+            percent(1.0 - max),
+        )
+    }
+}
+
+pub struct EpsOptions {
+    /// Size of a module in points.
+    pub module_size: f64,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub foreground: Cmyk,
+    /// Colour painted behind the symbol and its quiet zone, nothing is painted when `None`.
+    pub background: Option<Cmyk>,
+}
+
+impl Default for EpsOptions {
+    fn default() -> Self {
+        EpsOptions {
+            module_size: 2.0,
+            quiet_zone: 4,
+            foreground: Cmyk::BLACK,
+            background: None,
+        }
+    }
+}
+
+/// Renders the symbol as an Encapsulated PostScript file, the bounding box includes the quiet
+/// zone.
+pub fn render(qrcode: &QrCode, options: &EpsOptions) -> String {
+    let size = qrcode.size();
+    let modules = size + 2 * options.quiet_zone;
+    let extent = modules as f64 * options.module_size;
+
+    let mut eps = header(qrcode, extent, false);
+    eps.push_str("save\n/f { rectfill } bind def\n");
+    // draw in modules, from the bottom left corner of the quiet zone
+    let _ = writeln!(eps, "{0} {0} scale", options.module_size);
+    if let Some(background) = options.background {
+        let _ = writeln!(eps, "{}", background.to_postscript());
+        let _ = writeln!(eps, "0 0 {0} {0} f", modules);
+    }
+
+    let _ = writeln!(eps, "{}", options.foreground.to_postscript());
+    for (x, y, length) in dark_runs(qrcode) {
+        // PostScript coordinates start at the bottom
+        let _ = writeln!(
+            eps,
+            "{} {} {} 1 f",
+            x + options.quiet_zone,
+            size - 1 - y + options.quiet_zone,
+            length
+        );
+    }
+    eps.push_str("restore\nshowpage\n%%EOF\n");
+
+    eps
+}
+
+/// Renders the symbol painted with `style`, the style replaces the colours of the options and
+/// its colours are converted to CMYK. Gradients are drawn with `shfill`, which needs a
+/// PostScript level 3 interpreter.
+pub fn render_styled(qrcode: &QrCode, options: &EpsOptions, style: &Style) -> String {
+    let size = qrcode.size();
+    let modules = size + 2 * options.quiet_zone;
+    let extent = modules as f64 * options.module_size;
+    let shapes = region_shapes(qrcode, style);
+    let gradients = shapes
+        .iter()
+        .any(|shapes| !matches!(style.paint(shapes.region), Paint::Solid(_)));
+
+    let mut eps = header(qrcode, extent, gradients);
+    // a run of `length` modules from `(x, y)`, as `length x y r`
+    eps.push_str(
+        "save\n/r { moveto dup 0 rlineto 0 1 rlineto neg 0 rlineto closepath } bind def\n",
+    );
+    let _ = writeln!(eps, "{0} {0} scale", options.module_size);
+    if let Some(light) = style.light {
+        let _ = writeln!(eps, "{}", Cmyk::from(light).to_postscript());
+        let _ = writeln!(eps, "0 0 {0} {0} rectfill", modules);
+    }
+    // draw in modules from the top left corner of the symbol, with y growing downwards like SVG
+    let _ = writeln!(
+        eps,
+        "{} {} translate 1 -1 scale",
+        options.quiet_zone,
+        options.quiet_zone + size
+    );
+
+    for shapes in &shapes {
+        eps.push_str("newpath\n");
+        for (x, y, length) in &shapes.runs {
+            let _ = writeln!(eps, "{} {} {} r", length, x, y);
+        }
+        for outline in &shapes.outlines {
+            push_outline(&mut eps, outline);
+        }
+
+        let even_odd = if shapes.outlines.is_empty() { "" } else { "eo" };
+        let (shading_type, coords, from, to) = match style.paint(shapes.region) {
+            Paint::Solid(color) => {
+                let _ = writeln!(eps, "{}", Cmyk::from(color).to_postscript());
+                let _ = writeln!(eps, "{}fill", even_odd);
+                continue;
+            }
+            Paint::Linear { start, end, angle } => {
+                let ((x1, y1), (x2, y2)) = linear_endpoints(size, angle);
+                (2, [x1, y1, x2, y2].map(number).join(" "), start, end)
+            }
+            Paint::Radial { center, edge } => {
+                let middle = number(size as f64 / 2.0);
+                let coords = format!("{0} {0} 0 {0} {0} {1}", middle, number(radial_radius(size)));
+                (3, coords, center, edge)
+            }
+        };
+        let _ = writeln!(
+            eps,
+            "gsave {}clip\n<< /ShadingType {} /ColorSpace /DeviceCMYK /Coords [{}] /Function << \
+             /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >> /Extend [true true] >> \
+             shfill grestore",
+            even_odd,
+            shading_type,
+            coords,
+            Cmyk::from(from).components(),
+            Cmyk::from(to).components()
+        );
+    }
+    eps.push_str("restore\nshowpage\n%%EOF\n");
+
+    eps
+}
+
+/// DSC comments of a file whose bounding box is a square of side `extent`.
+fn header(qrcode: &QrCode, extent: f64, level_3: bool) -> String {
+    let mut eps = String::new();
+    eps.push_str("%!PS-Adobe-3.0 EPSF-3.0\n");
+    let _ = writeln!(eps, "%%BoundingBox: 0 0 {0} {0}", extent.ceil() as u32);
+    let _ = writeln!(eps, "%%HiResBoundingBox: 0 0 {0:.3} {0:.3}", extent);
+    eps.push_str("%%Creator: qrcode\n");
+    let _ = writeln!(eps, "%%Title: QR code version {}", qrcode.version());
+    if level_3 {
+        eps.push_str("%%LanguageLevel: 3\n");
+    }
+    eps.push_str("%%Pages: 1\n%%EndComments\n");
+    eps
+}
+
+/// Appends a closed subpath along `outline`, with `arct` for the rounded corners.
+fn push_outline(eps: &mut String, outline: &Outline) {
+    let Outline {
+        left,
+        top,
+        right,
+        bottom,
+        radii: [top_left, top_right, bottom_right, bottom_left],
+    } = *outline;
+
+    let _ = writeln!(eps, "{} {} moveto", number(left + top_left), number(top));
+    // every corner, then the next one to round it towards
+    for ((x1, y1), (x2, y2), radius) in [
+        ((right, top), (right, bottom), top_right),
+        ((right, bottom), (left, bottom), bottom_right),
+        ((left, bottom), (left, top), bottom_left),
+        ((left, top), (right, top), top_left),
+    ] {
+        if radius > 0.0 {
+            let _ = writeln!(
+                eps,
+                "{} {} {} {} {} arct",
+                number(x1),
+                number(y1),
+                number(x2),
+                number(y2),
+                number(radius)
+            );
+        } else {
+            let _ = writeln!(eps, "{} {} lineto", number(x1), number(y1));
+        }
+    }
+    eps.push_str("closepath\n");
+}
+
+/// `Renderer` for EPS, the scale is the module size in points and the light colour is painted
+/// behind the symbol.
+#[derive(Default)]
+pub struct Eps;
+
+impl Renderer for Eps {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        let (foreground, background) = options.colors();
+        let eps_options = EpsOptions {
+            module_size: options.scale as f64,
+            quiet_zone: options.quiet_zone,
+            foreground: foreground.into(),
+            background: Some(background.into()),
+        };
+        render(qrcode, &eps_options)
+    }
+}
+
+/// `Renderer` for `render_styled`, the scale is the module size in points and the style replaces
+/// the colours and inversion of the options.
+#[derive(Default)]
+pub struct StyledEps {
+    pub style: Style,
+}
+
+impl Renderer for StyledEps {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        let eps_options = EpsOptions {
+            module_size: options.scale as f64,
+            quiet_zone: options.quiet_zone,
+            ..EpsOptions::default()
+        };
+        render_styled(qrcode, &eps_options, &self.style)
+    }
+}
+
+impl QrCode {
+    pub fn to_eps(&self, options: &EpsOptions) -> String {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::style::EyeShape;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn render_writes_bounding_box_with_quiet_zone() {
+        let eps = test_qrcode().to_eps(&EpsOptions {
+            module_size: 2.5,
+            ..EpsOptions::default()
+        });
+        assert!(eps.starts_with("%!PS-Adobe-3.0 EPSF-3.0\n"));
+        // (21 + 2 * 4) * 2.5 points
+        assert!(eps.contains("%%BoundingBox: 0 0 73 73\n"));
+        assert!(eps.contains("%%HiResBoundingBox: 0 0 72.500 72.500\n"));
+        assert!(eps.ends_with("%%EOF\n"));
+    }
+
+    #[test]
+    fn render_uses_pure_black_by_default() {
+        let eps = test_qrcode().to_eps(&EpsOptions::default());
+        assert!(eps.contains("0 0 0 1 setcmykcolor\n"));
+        // top row of the top left finder pattern
+        assert!(eps.contains("4 24 7 1 f\n"));
+        assert_eq!(eps.matches("setcmykcolor").count(), 1);
+    }
+
+    #[test]
+    fn render_paints_background() {
+        let eps = test_qrcode().to_eps(&EpsOptions {
+            foreground: Cmyk::new(100, 50, 0, 20),
+            background: Some(Cmyk::WHITE),
+            ..EpsOptions::default()
+        });
+        assert!(eps.contains("0 0 0 0 setcmykcolor\n0 0 29 29 f\n"));
+        assert!(eps.contains("1 0.5 0 0.2 setcmykcolor\n"));
+    }
+
+    #[test]
+    fn render_styled_fills_gradients_with_shfill() {
+        let style = Style {
+            dark: Paint::Linear {
+                start: Color::rgb(255, 0, 0),
+                end: Color::rgb(0, 0, 255),
+                angle: 0.0,
+            },
+            eye_ball: Some(Paint::Solid(Color::rgb(0, 0, 128))),
+            eye_ball_shape: EyeShape::Circle,
+            ..Default::default()
+        };
+        let eps = render_styled(&test_qrcode(), &EpsOptions::default(), &style);
+        assert!(eps.contains("%%LanguageLevel: 3\n"));
+        assert!(eps.contains("0 0 0 0 setcmykcolor\n0 0 29 29 rectfill\n"));
+        assert!(eps.contains("4 25 translate 1 -1 scale\n"));
+        // top row of the top left finder pattern
+        assert!(eps.contains("7 0 0 r\n"));
+        assert!(eps.contains(
+            "gsave clip\n<< /ShadingType 2 /ColorSpace /DeviceCMYK /Coords [0 10.5 21 10.5] \
+             /Function << /FunctionType 2 /Domain [0 1] /C0 [0 1 1 0] /C1 [1 1 0 0] /N 1 >> \
+             /Extend [true true] >> shfill grestore\n"
+        ));
+        // the round ball of the top left finder pattern
+        assert!(eps.contains("3.5 2 moveto\n5 2 5 5 1.5 arct\n"));
+        assert!(eps.contains("1 1 0 0.5 setcmykcolor\neofill\n"));
+    }
+
+    #[test]
+    fn render_styled_needs_level_3_for_gradients_only() {
+        let eps = test_qrcode().render_with(StyledEps::default()).build();
+        assert!(!eps.contains("%%LanguageLevel"));
+        assert!(!eps.contains("shfill"));
+        assert!(eps.contains("0 0 0 1 setcmykcolor\nfill\n"));
+    }
+
+    #[test]
+    fn cmyk_converts_from_rgb() {
+        assert_eq!(Cmyk::from(Color::BLACK), Cmyk::BLACK);
+        assert_eq!(Cmyk::from(Color::WHITE), Cmyk::WHITE);
+        assert_eq!(Cmyk::from(Color::rgb(255, 0, 0)), Cmyk::new(0, 100, 100, 0));
+        assert_eq!(
+            Cmyk::from(Color::rgb(0, 0, 128)),
+            Cmyk::new(100, 100, 0, 50)
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use crate::qrcode::QrCode;
+use crate::render::png::{self, PngOptions};
+use crate::render::svg::{self, escape, SvgOptions};
+use crate::render::{base64, dark_at, dark_runs, Color};
+use std::fmt::Write;
+
+pub struct HtmlOptions {
+    /// Size of a module in CSS pixels.
+    pub module_size: u32,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub foreground: Color,
+    pub background: Color,
+    /// Alternative text of the `<img>` snippets.
+    pub alt: String,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions {
+            module_size: 4,
+            quiet_zone: 4,
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+            alt: "QR code".to_string(),
+        }
+    }
+}
+
+/// Runs of modules with the same colour in row `y`, quiet zone included, as `(dark, length)`.
+fn row_runs(qrcode: &QrCode, quiet_zone: u32, y: u32) -> Vec<(bool, u32)> {
+    let modules = qrcode.size() + 2 * quiet_zone;
+    let mut runs: Vec<(bool, u32)> = Vec::new();
+
+    for x in 0..modules {
+        let dark = dark_at(qrcode, quiet_zone, x, y);
+        match runs.last_mut() {
+            Some((previous, length)) if *previous == dark => *length += 1,
+            _ => runs.push((dark, 1)),
+        }
+    }
+
+    runs
+}
+
+/// A `<table>` with a `bgcolor` cell per run of modules, for mail clients like Outlook that
+/// ignore CSS layouts and images.
+pub fn table(qrcode: &QrCode, options: &HtmlOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let size = options.module_size;
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<table cellpadding=\"0\" cellspacing=\"0\" border=\"0\" width=\"{}\" \
+         style=\"border-collapse:collapse;table-layout:fixed\">",
+        modules * size
+    );
+    for y in 0..modules {
+        html.push_str("<tr>");
+        for (dark, length) in row_runs(qrcode, options.quiet_zone, y) {
+            let color = if dark {
+                options.foreground
+            } else {
+                options.background
+            };
+            if length > 1 {
+                let _ = write!(html, "<td colspan=\"{}\"", length);
+            } else {
+                html.push_str("<td");
+            }
+            let _ = write!(
+                html,
+                " width=\"{}\" height=\"{}\" bgcolor=\"{}\"></td>",
+                length * size,
+                size,
+                color.to_hex()
+            );
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+
+    html
+}
+
+/// A CSS grid on the background colour, with an element placed over every horizontal run of
+/// dark modules.
+pub fn grid(qrcode: &QrCode, options: &HtmlOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<div role=\"img\" aria-label=\"{}\" style=\"display:grid;\
+         grid-template-columns:repeat({},{}px);grid-template-rows:repeat({},{}px);\
+         width:{}px;background:{}\">",
+        escape(&options.alt),
+        modules,
+        options.module_size,
+        modules,
+        options.module_size,
+        modules * options.module_size,
+        options.background.to_hex()
+    );
+    let foreground = options.foreground.to_hex();
+    for (x, y, length) in dark_runs(qrcode) {
+        // grid lines start at 1
+        let _ = write!(
+            html,
+            "<div style=\"grid-area:{}/{}/span 1/span {};background:{}\"></div>",
+            y + options.quiet_zone + 1,
+            x + options.quiet_zone + 1,
+            length,
+            foreground
+        );
+    }
+    html.push_str("</div>");
+
+    html
+}
+
+fn img(mime: &str, data: &[u8], width: u32, alt: &str) -> String {
+    format!(
+        "<img src=\"data:{};base64,{}\" width=\"{}\" height=\"{}\" alt=\"{}\">",
+        mime,
+        base64(data),
+        width,
+        width,
+        escape(alt)
+    )
+}
+
+/// An `<img>` embedding a PNG of the symbol as a data URI.
+pub fn png_img(qrcode: &QrCode, options: &HtmlOptions) -> String {
+    let png = png::render(
+        qrcode,
+        &PngOptions {
+            scale: options.module_size,
+            quiet_zone: options.quiet_zone,
+            foreground: options.foreground,
+            background: options.background,
+        },
+    );
+    let width = (qrcode.size() + 2 * options.quiet_zone) * options.module_size;
+    img("image/png", &png, width, &options.alt)
+}
+
+/// An `<img>` embedding an SVG of the symbol as a data URI.
+pub fn svg_img(qrcode: &QrCode, options: &HtmlOptions) -> String {
+    let svg = svg::render(
+        qrcode,
+        &SvgOptions {
+            module_size: options.module_size,
+            quiet_zone: options.quiet_zone,
+            foreground: options.foreground,
+            background: options.background,
+            title: None,
+        },
+    );
+    let width = (qrcode.size() + 2 * options.quiet_zone) * options.module_size;
+    img("image/svg+xml", svg.as_bytes(), width, &options.alt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn table_merges_runs_with_colspan() {
+        let html = table(&test_qrcode(), &HtmlOptions::default());
+        assert!(html
+            .starts_with("<table cellpadding=\"0\" cellspacing=\"0\" border=\"0\" width=\"116\""));
+        assert_eq!(html.matches("<tr>").count(), 29);
+        // a quiet zone row is a single cell
+        assert!(html.contains(
+            "<tr><td colspan=\"29\" width=\"116\" height=\"4\" bgcolor=\"#ffffff\"></td></tr>"
+        ));
+        // the top row of the finder pattern after the quiet zone
+        assert!(html.contains(
+            "<tr><td colspan=\"4\" width=\"16\" height=\"4\" bgcolor=\"#ffffff\"></td>\
+             <td colspan=\"7\" width=\"28\" height=\"4\" bgcolor=\"#000000\"></td>"
+        ));
+    }
+
+    #[test]
+    fn grid_places_dark_runs() {
+        let options = HtmlOptions {
+            foreground: Color::rgb(0, 0, 128),
+            ..Default::default()
+        };
+        let html = grid(&test_qrcode(), &options);
+        assert!(html.contains("grid-template-columns:repeat(29,4px)"));
+        assert!(
+            html.contains("<div style=\"grid-area:5/5/span 1/span 7;background:#000080\"></div>")
+        );
+        assert_eq!(
+            html.matches("grid-area").count(),
+            dark_runs(&test_qrcode()).len()
+        );
+    }
+
+    #[test]
+    fn img_snippets_use_data_uris() {
+        let options = HtmlOptions {
+            alt: "Say \"hi\"".to_string(),
+            ..Default::default()
+        };
+        let png = png_img(&test_qrcode(), &options);
+        assert!(png.starts_with("<img src=\"data:image/png;base64,iVBORw0KGgo"));
+        assert!(png.ends_with("width=\"116\" height=\"116\" alt=\"Say &quot;hi&quot;\">"));
+
+        let svg = svg_img(&test_qrcode(), &options);
+        assert!(svg.starts_with("<img src=\"data:image/svg+xml;base64,PHN2ZyB4bWxucz0"));
+    }
+}
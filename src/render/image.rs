@@ -0,0 +1,64 @@
+//! Conversions into the `image` crate's buffers, enabled by the `image` feature.
+
+use ::image::{DynamicImage, GrayImage, RgbaImage};
+
+use crate::decode::{decode_rgba, Decoded};
+use crate::qrcode::QrCode;
+use crate::render::RenderOptions;
+
+impl QrCode {
+    /// Black on white grayscale image, `scale` pixels per module.
+    pub fn to_gray_image(&self, scale: u32, quiet_zone: u32) -> GrayImage {
+        let (width, height, pixels) = self.to_luma8(scale, quiet_zone);
+        GrayImage::from_raw(width, height, pixels).expect("pixel count matches the size")
+    }
+
+    /// Black on white RGBA image, `scale` pixels per module.
+    pub fn to_rgba_image(&self, scale: u32, quiet_zone: u32) -> RgbaImage {
+        let (width, height, pixels) = self.to_rgba8(scale, quiet_zone);
+        RgbaImage::from_raw(width, height, pixels).expect("pixel count matches the size")
+    }
+}
+
+impl From<&QrCode> for GrayImage {
+    /// Uses the default scale and quiet zone of [`RenderOptions`].
+    fn from(qrcode: &QrCode) -> Self {
+        let options = RenderOptions::default();
+        qrcode.to_gray_image(options.scale, options.quiet_zone)
+    }
+}
+
+impl From<&QrCode> for RgbaImage {
+    /// Uses the default scale and quiet zone of [`RenderOptions`].
+    fn from(qrcode: &QrCode) -> Self {
+        let options = RenderOptions::default();
+        qrcode.to_rgba_image(options.scale, options.quiet_zone)
+    }
+}
+
+/// Decodes an upright symbol from any image, transparent pixels are composited over white.
+pub fn decode(image: &DynamicImage) -> Result<Decoded, String> {
+    let rgba = image.to_rgba8();
+    decode_rgba(rgba.width(), rgba.height(), rgba.as_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn images_round_trip_through_the_decoder() {
+        let qrcode = test_qrcode();
+
+        let gray = GrayImage::from(&qrcode);
+        assert_eq!(gray.dimensions(), (232, 232));
+        let decoded = decode(&DynamicImage::ImageLuma8(gray)).unwrap();
+        assert_eq!(decoded.data, b"HELLO WORLD");
+
+        let rgba = qrcode.to_rgba_image(2, 4);
+        assert_eq!(rgba.get_pixel(8, 8).0, [0, 0, 0, 255]);
+        let decoded = decode(&DynamicImage::ImageRgba8(rgba)).unwrap();
+        assert_eq!(decoded.data, b"HELLO WORLD");
+    }
+}
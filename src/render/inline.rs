@@ -0,0 +1,233 @@
+use crate::qrcode::QrCode;
+use crate::render::png::{self, PngOptions};
+use crate::render::sixel::{self, SixelOptions};
+use crate::render::text::{self, TextOptions};
+use crate::render::{base64, Color};
+use std::io::IsTerminal;
+
+/// Kitty splits the payload of a transmission in chunks of at most 4096 bytes.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// How a terminal can show the symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Graphics {
+    /// Kitty graphics protocol.
+    Kitty,
+    /// iTerm2 inline images (OSC 1337), also understood by WezTerm.
+    ITerm2,
+    Sixel,
+    /// `▀▄█` half blocks, for every other terminal and when stdout is not a terminal.
+    Blocks,
+}
+
+impl Graphics {
+    /// Graphics supported by the terminal on stdout, guessed from `TERM`, `TERM_PROGRAM` and
+    /// `KITTY_WINDOW_ID`.
+    pub fn detect() -> Graphics {
+        detect_with(
+            |name| std::env::var(name).ok(),
+            std::io::stdout().is_terminal(),
+        )
+    }
+}
+
+fn detect_with(var: impl Fn(&str) -> Option<String>, is_terminal: bool) -> Graphics {
+    if !is_terminal {
+        return Graphics::Blocks;
+    }
+
+    let term = var("TERM").unwrap_or_default();
+    let term_program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" {
+        Graphics::Kitty
+    } else if term_program == "iTerm.app" || term_program == "WezTerm" {
+        Graphics::ITerm2
+    } else if term.starts_with("foot") || term.starts_with("mlterm") {
+        Graphics::Sixel
+    } else {
+        Graphics::Blocks
+    }
+}
+
+pub struct InlineOptions {
+    /// Size of a module in pixels.
+    pub scale: u32,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+}
+
+impl Default for InlineOptions {
+    fn default() -> Self {
+        InlineOptions {
+            scale: 8,
+            quiet_zone: 4,
+        }
+    }
+}
+
+fn encoded_png(qrcode: &QrCode, options: &InlineOptions) -> String {
+    base64(&png::render(
+        qrcode,
+        &PngOptions {
+            scale: options.scale,
+            quiet_zone: options.quiet_zone,
+            ..Default::default()
+        },
+    ))
+}
+
+/// Transmits and displays a PNG of the symbol with the Kitty graphics protocol, followed by a
+/// newline.
+pub fn kitty(qrcode: &QrCode, options: &InlineOptions) -> String {
+    kitty_escapes(&encoded_png(qrcode, options))
+}
+
+fn kitty_escapes(payload: &str) -> String {
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    let mut escapes = String::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        // only the first chunk has the action and format, `m=1` announces more chunks
+        let control = if i == 0 { "a=T,f=100," } else { "" };
+        let more = (i + 1 < chunks.len()) as u8;
+        escapes.push_str(&format!("\x1b_G{}m={};", control, more));
+        escapes.push_str(std::str::from_utf8(chunk).unwrap());
+        escapes.push_str("\x1b\\");
+    }
+
+    escapes.push('\n');
+    escapes
+}
+
+/// Displays a PNG of the symbol with the iTerm2 inline image escape, followed by a newline.
+pub fn iterm2(qrcode: &QrCode, options: &InlineOptions) -> String {
+    let png = png::render(
+        qrcode,
+        &PngOptions {
+            scale: options.scale,
+            quiet_zone: options.quiet_zone,
+            ..Default::default()
+        },
+    );
+    format!(
+        "\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:{}\x07\n",
+        png.len(),
+        base64(&png)
+    )
+}
+
+/// Renders the symbol with the best graphics `graphics` supports.
+pub fn render(qrcode: &QrCode, graphics: Graphics, options: &InlineOptions) -> String {
+    match graphics {
+        Graphics::Kitty => kitty(qrcode, options),
+        Graphics::ITerm2 => iterm2(qrcode, options),
+        Graphics::Sixel => {
+            let sixel_options = SixelOptions {
+                scale: options.scale,
+                quiet_zone: options.quiet_zone,
+                dark: Color::BLACK,
+                light: Color::WHITE,
+            };
+            sixel::render(qrcode, &sixel_options) + "\n"
+        }
+        Graphics::Blocks => text::half_blocks(
+            qrcode,
+            &TextOptions {
+                quiet_zone: options.quiet_zone,
+                invert: true,
+            },
+        ),
+    }
+}
+
+/// Renders the symbol with the graphics detected for stdout.
+pub fn render_for_stdout(qrcode: &QrCode, options: &InlineOptions) -> String {
+    render(qrcode, Graphics::detect(), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::EcLevel;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskPattern;
+    use crate::preprocessor::Preprocessor;
+
+    fn qrcode(data: &str) -> QrCode {
+        Preprocessor::new(data, Encoding::Byte, EcLevel::H, MaskPattern::Checkerboard)
+            .generate_qrcode()
+    }
+
+    #[test]
+    fn kitty_splits_payload_in_chunks() {
+        let escapes = kitty(&qrcode("https://example.com"), &InlineOptions::default());
+        assert!(escapes.starts_with("\x1b_Ga=T,f=100,m=0;iVBORw0KGgo"));
+        assert!(escapes.ends_with("\x1b\\\n"));
+
+        let payload = "A".repeat(2 * KITTY_CHUNK_SIZE + 10);
+        let escapes = kitty_escapes(&payload);
+        let chunks: Vec<&str> = escapes.trim_end().split("\x1b\\").collect();
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks[0].starts_with("\x1b_Ga=T,f=100,m=1;"));
+        assert!(chunks[1].starts_with("\x1b_Gm=1;"));
+        assert!(chunks[2].starts_with("\x1b_Gm=0;"));
+
+        let joined: String = chunks
+            .iter()
+            .map(|chunk| chunk.split_once(';').map_or("", |(_, data)| data))
+            .collect();
+        assert_eq!(joined, payload);
+    }
+
+    #[test]
+    fn iterm2_announces_png_size() {
+        let qrcode = qrcode("HELLO");
+        let options = InlineOptions::default();
+        let png_size = png::render(
+            &qrcode,
+            &PngOptions {
+                scale: 8,
+                quiet_zone: 4,
+                ..Default::default()
+            },
+        )
+        .len();
+        let escape = iterm2(&qrcode, &options);
+        assert!(escape.starts_with(&format!(
+            "\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:iVBORw0KGgo",
+            png_size
+        )));
+        assert!(escape.ends_with("\x07\n"));
+    }
+
+    #[test]
+    fn detect_guesses_from_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        let kitty = env(&[("KITTY_WINDOW_ID", "1"), ("TERM", "xterm-256color")]);
+        assert_eq!(detect_with(kitty, true), Graphics::Kitty);
+        assert_eq!(
+            detect_with(env(&[("TERM", "xterm-kitty")]), true),
+            Graphics::Kitty
+        );
+        assert_eq!(
+            detect_with(env(&[("TERM_PROGRAM", "iTerm.app")]), true),
+            Graphics::ITerm2
+        );
+        assert_eq!(detect_with(env(&[("TERM", "foot")]), true), Graphics::Sixel);
+        assert_eq!(
+            detect_with(env(&[("TERM", "xterm-256color")]), true),
+            Graphics::Blocks
+        );
+        assert_eq!(
+            detect_with(env(&[("KITTY_WINDOW_ID", "1")]), false),
+            Graphics::Blocks
+        );
+    }
+}
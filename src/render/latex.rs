@@ -0,0 +1,229 @@
+use crate::qrcode::QrCode;
+use crate::render::{dark_runs, number, Color, RenderOptions, Renderer};
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatexOutput {
+    /// `tikzpicture` environment, needs the `tikz` package.
+    Tikz,
+    /// Plain TeX `\vrule` boxes drawn in the current text colour, no package needed.
+    Rules,
+}
+
+pub struct LatexOptions {
+    pub output: LatexOutput,
+    /// Size of a module as a TeX dimension, such as `1mm` or `0.5em`.
+    pub module_size: String,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    /// Colours of the TikZ picture, the background is filled only when set.
+    pub foreground: Color,
+    pub background: Option<Color>,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        LatexOptions {
+            output: LatexOutput::Tikz,
+            module_size: "1mm".to_string(),
+            quiet_zone: 4,
+            foreground: Color::BLACK,
+            background: None,
+        }
+    }
+}
+
+/// Renders the symbol as a LaTeX snippet to `\input` in a document. Dark modules are merged in
+/// horizontal runs, a rectangle or rule each.
+pub fn render(qrcode: &QrCode, options: &LatexOptions) -> String {
+    match options.output {
+        LatexOutput::Tikz => tikz(qrcode, options),
+        LatexOutput::Rules => rules(qrcode, options),
+    }
+}
+
+fn define_color(latex: &mut String, name: &str, color: Color) {
+    let _ = writeln!(
+        latex,
+        "\\definecolor{{{}}}{{RGB}}{{{},{},{}}}",
+        name, color.r, color.g, color.b
+    );
+}
+
+/// Coordinates are in modules, with y growing downwards like the symbol.
+fn tikz(qrcode: &QrCode, options: &LatexOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+
+    let mut latex = String::new();
+    let _ = writeln!(
+        latex,
+        "% QR code version {}, {} modules with the quiet zone",
+        qrcode.version(),
+        modules
+    );
+    let _ = writeln!(
+        latex,
+        "\\begin{{tikzpicture}}[x={0},y=-{0}]",
+        options.module_size
+    );
+    if let Some(background) = options.background {
+        define_color(&mut latex, "qrlight", background);
+        let _ = writeln!(latex, "\\fill[qrlight] (0,0) rectangle ({0},{0});", modules);
+    } else {
+        // keeps the quiet zone in the bounding box
+        let _ = writeln!(
+            latex,
+            "\\path[use as bounding box] (0,0) rectangle ({0},{0});",
+            modules
+        );
+    }
+    define_color(&mut latex, "qrdark", options.foreground);
+    latex.push_str("\\fill[qrdark]");
+    for (x, y, length) in dark_runs(qrcode) {
+        let (x, y) = (x + options.quiet_zone, y + options.quiet_zone);
+        let _ = write!(latex, "\n  ({},{}) rectangle +({},1)", x, y, length);
+    }
+    latex.push_str(";\n\\end{tikzpicture}\n");
+
+    latex
+}
+
+/// A `\vbox` of rows, each an `\hbox` of the symbol width with kerns for light modules and rules
+/// for dark runs. The scratch register `\dimen0` holds the module size inside a group.
+fn rules(qrcode: &QrCode, options: &LatexOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let runs = dark_runs(qrcode);
+
+    let mut latex = String::new();
+    let _ = writeln!(
+        latex,
+        "% QR code version {}, {} modules with the quiet zone",
+        qrcode.version(),
+        modules
+    );
+    let _ = writeln!(
+        latex,
+        "{{\\dimen0={}\\relax\\vbox{{\\offinterlineskip",
+        options.module_size
+    );
+    let quiet_zone = |latex: &mut String| {
+        if options.quiet_zone > 0 {
+            let _ = writeln!(
+                latex,
+                "\\hbox to {}\\dimen0{{\\vrule width 0pt height {}\\dimen0\\hfil}}",
+                modules, options.quiet_zone
+            );
+        }
+    };
+
+    quiet_zone(&mut latex);
+    let mut runs = runs.iter().peekable();
+    for y in 0..qrcode.size() {
+        let _ = write!(
+            latex,
+            "\\hbox to {}\\dimen0{{\\vrule width 0pt height \\dimen0",
+            modules
+        );
+        let mut end = 0;
+        while let Some(&(x, _, length)) = runs.next_if(|run| run.1 == y) {
+            let gap = x + options.quiet_zone - end;
+            if gap > 0 {
+                let _ = write!(latex, "\\kern{}\\dimen0", gap);
+            }
+            let _ = write!(latex, "\\vrule width {}\\dimen0", length);
+            end = x + options.quiet_zone + length;
+        }
+        latex.push_str("\\hfil}\n");
+    }
+    quiet_zone(&mut latex);
+    latex.push_str("}}\n");
+
+    latex
+}
+
+/// `Renderer` for a TikZ picture, the scale is the module size in points.
+#[derive(Default)]
+pub struct Tikz;
+
+impl Renderer for Tikz {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        let (foreground, background) = options.colors();
+        let latex_options = LatexOptions {
+            output: LatexOutput::Tikz,
+            module_size: format!("{}pt", number(options.scale as f64)),
+            quiet_zone: options.quiet_zone,
+            foreground,
+            background: Some(background),
+        };
+        render(qrcode, &latex_options)
+    }
+}
+
+impl QrCode {
+    pub fn to_latex(&self, options: &LatexOptions) -> String {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn tikz_draws_a_rectangle_per_run() {
+        let latex = test_qrcode().to_latex(&LatexOptions::default());
+        assert!(latex.starts_with(
+            "% QR code version 1, 29 modules with the quiet zone\n\
+             \\begin{tikzpicture}[x=1mm,y=-1mm]\n\
+             \\path[use as bounding box] (0,0) rectangle (29,29);\n\
+             \\definecolor{qrdark}{RGB}{0,0,0}\n\
+             \\fill[qrdark]\n  (4,4) rectangle +(7,1)"
+        ));
+        assert!(latex.ends_with(";\n\\end{tikzpicture}\n"));
+        assert_eq!(
+            latex.matches("rectangle +").count(),
+            dark_runs(&test_qrcode()).len()
+        );
+    }
+
+    #[test]
+    fn tikz_renderer_fills_the_background() {
+        let latex = test_qrcode()
+            .render::<Tikz>()
+            .scale(2)
+            .quiet_zone(1)
+            .build();
+        assert!(latex.contains("[x=2pt,y=-2pt]"));
+        assert!(latex.contains(
+            "\\definecolor{qrlight}{RGB}{255,255,255}\n\\fill[qrlight] (0,0) rectangle (23,23);"
+        ));
+    }
+
+    #[test]
+    fn rules_kern_over_light_modules() {
+        let options = LatexOptions {
+            output: LatexOutput::Rules,
+            module_size: "0.5em".to_string(),
+            quiet_zone: 2,
+            ..Default::default()
+        };
+        let latex = test_qrcode().to_latex(&options);
+        let lines: Vec<&str> = latex.lines().collect();
+        // comment, opening, quiet zone, 21 rows, quiet zone, closing
+        assert_eq!(lines.len(), 26);
+        assert_eq!(lines[1], "{\\dimen0=0.5em\\relax\\vbox{\\offinterlineskip");
+        assert_eq!(
+            lines[2],
+            "\\hbox to 25\\dimen0{\\vrule width 0pt height 2\\dimen0\\hfil}"
+        );
+        // top row: finder, light, ..., finder
+        assert!(lines[3].starts_with(
+            "\\hbox to 25\\dimen0{\\vrule width 0pt height \\dimen0\\kern2\\dimen0\\vrule width \
+             7\\dimen0\\kern"
+        ));
+        assert_eq!(lines[25], "}}");
+    }
+}
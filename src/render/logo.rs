@@ -0,0 +1,428 @@
+use crate::bit::{Bit, ModuleRole};
+use crate::ec::{ec_codewords_per_block, EcLevel};
+use crate::encoder::Encoder;
+use crate::encoding::Encoding;
+use crate::layout::codeword_layout;
+use crate::mask::MaskPattern;
+use crate::qrcode::QrCode;
+use crate::render::base64;
+use crate::render::png::{encode_rgba, PngOptions};
+use crate::render::style::{Paint, Style};
+use crate::render::svg::{self, SvgOptions};
+use std::fmt::Write;
+
+/// EC levels from the least to the most redundant.
+const EC_LEVELS: [EcLevel; 4] = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+
+pub enum LogoImage {
+    /// SVG document, only for SVG output.
+    Svg(String),
+    /// Encoded PNG file, only for SVG output.
+    Png(Vec<u8>),
+    /// 8-bit RGBA pixels, row by row, for SVG and PNG output.
+    Rgba {
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    },
+}
+
+pub struct Logo {
+    pub image: LogoImage,
+    /// Side of the reserved region as a fraction of the symbol side, quiet zone excluded.
+    pub size: f64,
+    /// Light modules between the edge of the region and the image.
+    pub margin: u32,
+}
+
+/// What to do when the EC level can't absorb the modules hidden by the logo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcPolicy {
+    /// Fail with the requested level.
+    Require,
+    /// Try the next levels up to H.
+    Upgrade,
+}
+
+/// Centred square region of a symbol of `size` modules covering `fraction` of its side, as
+/// `(left, top, side)`. The side has the parity of the symbol so the region is centred exactly.
+pub fn centred_region(size: u32, fraction: f64) -> (u32, u32, u32) {
+    let mut side = (size as f64 * fraction).round() as u32;
+    if side % 2 != size % 2 {
+        side += 1;
+    }
+    let side = side.min(size);
+    let start = (size - side) / 2;
+    (start, start, side)
+}
+
+/// Number of codewords per block with at least one module inside `region`, from the codeword
+/// layout of `QrCode::fill`.
+pub fn covered_codewords(
+    version: u8,
+    ec_level: EcLevel,
+    region: (u32, u32, u32),
+) -> Result<Vec<usize>, String> {
+    let (left, top, side) = region;
+    let inside = |&(x, y): &(u32, u32)| x >= left && x < left + side && y >= top && y < top + side;
+
+    let layout = codeword_layout(version, ec_level)?;
+    let blocks = layout.codewords.iter().map(|c| c.block).max().unwrap_or(0) + 1;
+    let mut covered = vec![0; blocks];
+    for codeword in &layout.codewords {
+        if codeword.modules.iter().any(inside) {
+            covered[codeword.block] += 1;
+        }
+    }
+    Ok(covered)
+}
+
+/// Checks that the codewords hidden by `region` stay within half the EC codewords of every
+/// block, which is what error correction can repair.
+pub fn check_budget(version: u8, ec_level: EcLevel, region: (u32, u32, u32)) -> Result<(), String> {
+    let budget = ec_codewords_per_block(version, &ec_level) / 2;
+    for (block, &covered) in covered_codewords(version, ec_level, region)?
+        .iter()
+        .enumerate()
+    {
+        if covered > budget {
+            return Err(format!(
+                "Logo covers {} codewords of block {}, more than the {} EC level {:?} can correct.",
+                covered, block, budget, ec_level
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Encodes `data` with `encoder` and a centred region reserved for `logo`. The modules of the
+/// region are made light, and the region can't cover function patterns other than alignment
+/// patterns.
+pub fn encode(
+    encoder: &Encoder,
+    data: &str,
+    encoding: Encoding,
+    ec_level: EcLevel,
+    mask_pattern: MaskPattern,
+    logo: &Logo,
+    policy: EcPolicy,
+) -> Result<QrCode, String> {
+    let levels = EC_LEVELS
+        .iter()
+        .skip_while(|level| level.ordinal() != ec_level.ordinal())
+        .take(if policy == EcPolicy::Upgrade { 4 } else { 1 });
+
+    let mut error = String::new();
+    for &level in levels {
+        let mut qrcode = match encoder.encode(data, encoding, level, mask_pattern) {
+            Ok(qrcode) => qrcode,
+            // higher levels hold less data, the budget of the previous level explains more
+            Err(err) => {
+                if error.is_empty() {
+                    error = err;
+                }
+                break;
+            }
+        };
+        let region = centred_region(qrcode.size(), logo.size);
+        match check_budget(qrcode.version(), level, region) {
+            Ok(()) => {
+                clear_region(&mut qrcode, region)?;
+                return Ok(qrcode);
+            }
+            Err(err) => error = err,
+        }
+    }
+    Err(error)
+}
+
+impl Encoder {
+    /// Same as `logo::encode` with this encoder.
+    pub fn encode_with_logo(
+        &self,
+        data: &str,
+        encoding: Encoding,
+        ec_level: EcLevel,
+        mask_pattern: MaskPattern,
+        logo: &Logo,
+        policy: EcPolicy,
+    ) -> Result<QrCode, String> {
+        encode(self, data, encoding, ec_level, mask_pattern, logo, policy)
+    }
+}
+
+fn clear_region(qrcode: &mut QrCode, (left, top, side): (u32, u32, u32)) -> Result<(), String> {
+    let size = qrcode.size();
+    for y in top..top + side {
+        for x in left..left + side {
+            let role = qrcode.role(x, y).unwrap();
+            if role.is_functional() && role != ModuleRole::Alignment {
+                return Err(format!("Logo covers the {:?} pattern.", role));
+            }
+            qrcode.data[(y * size + x) as usize] = Bit::Zero(role);
+        }
+    }
+    Ok(())
+}
+
+/// Where the image goes in modules, quiet zone included, as `(left, top, side)`.
+fn image_area(qrcode: &QrCode, logo: &Logo, quiet_zone: u32) -> (u32, u32, u32) {
+    let (left, top, side) = centred_region(qrcode.size(), logo.size);
+    let margin = logo.margin.min(side / 2);
+    (
+        left + quiet_zone + margin,
+        top + quiet_zone + margin,
+        side - 2 * margin,
+    )
+}
+
+/// Renders a symbol from `encode` as SVG with the logo on top of its reserved region. The image
+/// is linked with `xlink:href`, which SVG 1.1 viewers need.
+pub fn render_svg(qrcode: &QrCode, logo: &Logo, options: &SvgOptions) -> String {
+    let mut svg = svg::render(qrcode, options).replacen(
+        "xmlns=\"http://www.w3.org/2000/svg\"",
+        "xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\"",
+        1,
+    );
+    let (left, top, side) = image_area(qrcode, logo, options.quiet_zone);
+
+    let (mime, data) = match &logo.image {
+        LogoImage::Svg(document) => ("image/svg+xml", document.as_bytes().to_vec()),
+        LogoImage::Png(png) => ("image/png", png.clone()),
+        LogoImage::Rgba {
+            width,
+            height,
+            pixels,
+        } => ("image/png", encode_rgba(*width, *height, pixels)),
+    };
+    let mut image = String::new();
+    let _ = write!(
+        image,
+        "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" xlink:href=\"data:{};base64,{}\"/>",
+        left,
+        top,
+        side,
+        side,
+        mime,
+        base64(&data)
+    );
+
+    let end = svg.rfind("</svg>").unwrap();
+    svg.insert_str(end, &image);
+    svg
+}
+
+/// Renders a symbol from `encode` as an RGBA PNG with the logo scaled into its reserved region.
+/// Only `LogoImage::Rgba` can be drawn, as images are not decoded.
+pub fn render_png(qrcode: &QrCode, logo: &Logo, options: &PngOptions) -> Result<Vec<u8>, String> {
+    let (width, pixels) = rgba_pixels(qrcode, logo, options)?;
+    Ok(encode_rgba(width, width, &pixels))
+}
+
+fn rgba_pixels(
+    qrcode: &QrCode,
+    logo: &Logo,
+    options: &PngOptions,
+) -> Result<(u32, Vec<u8>), String> {
+    let (pixels, (width, height)) = match &logo.image {
+        LogoImage::Rgba {
+            width,
+            height,
+            pixels,
+        } => (pixels, (*width, *height)),
+        _ => return Err("Only RGBA logos can be drawn into a PNG.".to_string()),
+    };
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|count| count.checked_mul(4));
+    if expected != Some(pixels.len()) || width == 0 || height == 0 {
+        return Err("Logo pixel count does not match its size.".to_string());
+    }
+
+    let scale = options.scale.max(1);
+    let style = Style {
+        dark: Paint::Solid(options.foreground),
+        light: Some(options.background),
+        ..Default::default()
+    };
+    let (image_width, mut image) = style.rgba_pixels(qrcode, scale, options.quiet_zone);
+
+    // fit the logo in the area keeping its aspect ratio, and blend it over the symbol
+    let (left, top, side) = image_area(qrcode, logo, options.quiet_zone);
+    let area = side * scale;
+    let fit = area as f64 / width.max(height) as f64;
+    let (logo_width, logo_height) = ((width as f64 * fit) as u32, (height as f64 * fit) as u32);
+    let origin_x = left * scale + (area - logo_width) / 2;
+    let origin_y = top * scale + (area - logo_height) / 2;
+    for y in 0..logo_height {
+        for x in 0..logo_width {
+            let source_x = ((x as f64 / fit) as u32).min(width - 1);
+            let source_y = ((y as f64 / fit) as u32).min(height - 1);
+            let source = ((source_y * width + source_x) * 4) as usize;
+            let target = (((origin_y + y) * image_width + origin_x + x) * 4) as usize;
+            let alpha = pixels[source + 3] as u32;
+            for channel in 0..3 {
+                let blended = (pixels[source + channel] as u32 * alpha
+                    + image[target + channel] as u32 * (255 - alpha))
+                    / 255;
+                image[target + channel] = blended as u8;
+            }
+            image[target + 3] = 255;
+        }
+    }
+
+    Ok((image_width, image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::decode_rgba;
+
+    const DATA: &str = "https://example.com/some/longer/path";
+
+    fn red_logo(size: f64) -> Logo {
+        Logo {
+            image: LogoImage::Rgba {
+                width: 2,
+                height: 2,
+                pixels: [255, 0, 0, 255].repeat(4),
+            },
+            size,
+            margin: 1,
+        }
+    }
+
+    #[test]
+    fn centred_region_keeps_symbol_parity() {
+        assert_eq!(centred_region(21, 0.0), (10, 10, 1));
+        assert_eq!(centred_region(21, 0.2), (8, 8, 5));
+        assert_eq!(centred_region(25, 0.3), (8, 8, 9));
+        assert_eq!(centred_region(21, 2.0), (0, 0, 21));
+    }
+
+    #[test]
+    fn check_budget_counts_codewords_per_block() {
+        let version = 5;
+        let small = centred_region(37, 0.1);
+        let covered = covered_codewords(version, EcLevel::H, small).unwrap();
+        assert_eq!(covered.len(), 4);
+        assert!(covered.iter().sum::<usize>() > 0);
+        assert!(check_budget(version, EcLevel::H, small).is_ok());
+
+        let large = centred_region(37, 0.6);
+        let err = check_budget(version, EcLevel::L, large).unwrap_err();
+        assert!(err.contains("EC level L can correct"));
+    }
+
+    #[test]
+    fn encode_refuses_or_upgrades_ec_level() {
+        let encoder = Encoder::new();
+        let logo = red_logo(0.3);
+        let mask = MaskPattern::Checkerboard;
+        assert!(encode(
+            &encoder,
+            DATA,
+            Encoding::Byte,
+            EcLevel::L,
+            mask,
+            &logo,
+            EcPolicy::Require
+        )
+        .is_err());
+
+        let qrcode = encoder
+            .encode_with_logo(
+                DATA,
+                Encoding::Byte,
+                EcLevel::L,
+                mask,
+                &logo,
+                EcPolicy::Upgrade,
+            )
+            .unwrap();
+        assert!(qrcode.ec_level().ordinal() > EcLevel::L.ordinal());
+        // the region is cleared, and error correction still recovers the data
+        let (left, top, _) = centred_region(qrcode.size(), 0.3);
+        assert!(!qrcode.get(left, top).unwrap().value());
+        assert_eq!(qrcode.decode().unwrap().data, DATA.as_bytes());
+    }
+
+    #[test]
+    fn upgrade_reports_budget_when_data_outgrows_higher_levels() {
+        // fits version 40 at EC level Q but not at H
+        let data = "a".repeat(1500);
+        let err = encode(
+            &Encoder::new(),
+            &data,
+            Encoding::Byte,
+            EcLevel::Q,
+            MaskPattern::Checkerboard,
+            &red_logo(0.5),
+            EcPolicy::Upgrade,
+        )
+        .err()
+        .unwrap();
+        assert!(err.contains("EC level Q can correct"));
+    }
+
+    #[test]
+    fn rendered_logo_still_decodes() {
+        let logo = red_logo(0.25);
+        let qrcode = encode(
+            &Encoder::new(),
+            DATA,
+            Encoding::Byte,
+            EcLevel::H,
+            MaskPattern::Checkerboard,
+            &logo,
+            EcPolicy::Require,
+        )
+        .unwrap();
+
+        // version 5, a region of 9 modules at 14 and a margin of 1
+        let svg = render_svg(&qrcode, &logo, &SvgOptions::default());
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\""
+        ));
+        assert!(svg.contains("\"/><image x=\"19\" y=\"19\" width=\"7\" height=\"7\" xlink:href=\"data:image/png;base64,iVBORw0KGgo"));
+        assert!(svg.ends_with("\"/></svg>\n"));
+
+        let png = render_png(&qrcode, &logo, &PngOptions::default()).unwrap();
+        assert_eq!(png[24..29], [8, 6, 0, 0, 0]);
+        let (width, pixels) = rgba_pixels(&qrcode, &logo, &PngOptions::default()).unwrap();
+        let (left, top, _) = image_area(&qrcode, &logo, 4);
+        let centre = (((top * 8 + 4) * width + left * 8 + 4) * 4) as usize;
+        assert_eq!(pixels[centre..centre + 4], [255, 0, 0, 255]);
+        assert_eq!(
+            decode_rgba(width, width, &pixels).unwrap().data,
+            DATA.as_bytes()
+        );
+
+        let svg_logo = Logo {
+            image: LogoImage::Svg("<svg/>".to_string()),
+            ..red_logo(0.25)
+        };
+        assert!(render_png(&qrcode, &svg_logo, &PngOptions::default()).is_err());
+
+        // a zero scale draws the logo over the symbol at scale 1
+        let unscaled = |scale| PngOptions {
+            scale,
+            ..Default::default()
+        };
+        assert_eq!(
+            rgba_pixels(&qrcode, &logo, &unscaled(0)).unwrap(),
+            rgba_pixels(&qrcode, &logo, &unscaled(1)).unwrap()
+        );
+
+        let overflowing = Logo {
+            image: LogoImage::Rgba {
+                width: u32::MAX,
+                height: u32::MAX,
+                pixels: vec![0; 4],
+            },
+            ..red_logo(0.25)
+        };
+        assert!(render_png(&qrcode, &overflowing, &PngOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::qrcode::QrCode;
+
+pub mod ansi;
+pub mod bmp;
+pub mod caption;
+mod deflate;
+pub mod dxf;
+pub mod eps;
+pub mod html;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod inline;
+pub mod latex;
+pub mod logo;
+pub mod netpbm;
+pub mod pdf;
+pub mod png;
+pub mod print;
+pub mod raster;
+pub mod sixel;
+pub mod style;
+pub mod svg;
+pub mod text;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    /// `#rrggbb` notation used by SVG, CSS and HTML.
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// Options shared by every renderer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderOptions {
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    /// Size of a module: pixels for raster images, user units for SVG, points for PDF and EPS.
+    /// Text renderers ignore it.
+    pub scale: u32,
+    pub dark: Color,
+    pub light: Color,
+    /// Swaps dark and light modules, off by default. Text renderers draw dark modules with
+    /// glyphs, so terminals with light text on a dark background need this, and `Display` turns
+    /// it on.
+    pub invert: bool,
+}
+
+impl RenderOptions {
+    /// Colours of the dark and light modules, once inverted.
+    pub fn colors(&self) -> (Color, Color) {
+        if self.invert {
+            (self.light, self.dark)
+        } else {
+            (self.dark, self.light)
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            quiet_zone: 4,
+            scale: 8,
+            dark: Color::BLACK,
+            light: Color::WHITE,
+            invert: false,
+        }
+    }
+}
+
+/// A backend drawing a symbol, anything implementing it can be used with `QrCode::render`.
+pub trait Renderer {
+    type Output;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Self::Output;
+}
+
+/// Builder returned by `QrCode::render`, the options start from `RenderOptions::default()`.
+pub struct Render<'a, R: Renderer> {
+    qrcode: &'a QrCode,
+    renderer: R,
+    options: RenderOptions,
+}
+
+impl<R: Renderer> Render<'_, R> {
+    pub fn quiet_zone(mut self, quiet_zone: u32) -> Self {
+        self.options.quiet_zone = quiet_zone;
+        self
+    }
+
+    pub fn scale(mut self, scale: u32) -> Self {
+        self.options.scale = scale;
+        self
+    }
+
+    pub fn dark_color(mut self, color: Color) -> Self {
+        self.options.dark = color;
+        self
+    }
+
+    pub fn light_color(mut self, color: Color) -> Self {
+        self.options.light = color;
+        self
+    }
+
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.options.invert = invert;
+        self
+    }
+
+    pub fn build(self) -> R::Output {
+        self.renderer.render(self.qrcode, &self.options)
+    }
+}
+
+impl QrCode {
+    /// Starts rendering with a backend that has a default configuration, e.g.
+    /// `qrcode.render::<Svg>().quiet_zone(2).build()`.
+    pub fn render<R: Renderer + Default>(&self) -> Render<'_, R> {
+        self.render_with(R::default())
+    }
+
+    /// Starts rendering with a configured backend.
+    pub fn render_with<R: Renderer>(&self, renderer: R) -> Render<'_, R> {
+        Render {
+            qrcode: self,
+            renderer,
+            options: RenderOptions::default(),
+        }
+    }
+}
+
+/// Horizontal runs of dark modules as `(x, y, length)`, in modules from the top left corner of
+/// the symbol, row by row.
+pub(crate) fn dark_runs(qrcode: &QrCode) -> Vec<(u32, u32, u32)> {
+    let size = qrcode.size();
+    let mut runs = Vec::new();
+
+    for (y, row) in qrcode.data.chunks(size as usize).enumerate() {
+        let mut start = None;
+        for x in 0..=size {
+            let dark = row.get(x as usize).is_some_and(|bit| bit.value());
+            match (dark, start) {
+                (true, None) => start = Some(x),
+                (false, Some(begin)) => {
+                    runs.push((begin, y as u32, x - begin));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    runs
+}
+
+/// Whether the module at `(x, y)` is dark, with coordinates counted from the top left corner of
+/// the quiet zone. Modules of the quiet zone and beyond are light.
+pub(crate) fn dark_at(qrcode: &QrCode, quiet_zone: u32, x: u32, y: u32) -> bool {
+    match (x.checked_sub(quiet_zone), y.checked_sub(quiet_zone)) {
+        (Some(x), Some(y)) => qrcode.get(x, y).is_some_and(|bit| bit.value()),
+        _ => false,
+    }
+}
+
+/// Formats a number with at most 3 decimals and no trailing zeros.
+pub(crate) fn number(value: f64) -> String {
+    let formatted = format!("{:.3}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Standard base64 with padding, for inline images and data URIs.
+pub(crate) fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// "HELLO WORLD" at EC level Q with the checkerboard mask, the symbol most renderer tests draw.
+#[cfg(test)]
+pub(crate) fn test_qrcode() -> QrCode {
+    use crate::ec::EcLevel;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskPattern;
+    use crate::preprocessor::Preprocessor;
+
+    Preprocessor::new(
+        "HELLO WORLD",
+        Encoding::Alphanumeric,
+        EcLevel::Q,
+        MaskPattern::Checkerboard,
+    )
+    .generate_qrcode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Count;
+
+    impl Renderer for Count {
+        type Output = (u32, bool);
+
+        fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> (u32, bool) {
+            let modules = qrcode.size() + 2 * options.quiet_zone;
+            (modules * options.scale, options.colors().0 == Color::WHITE)
+        }
+    }
+
+    #[test]
+    fn custom_renderers_get_builder_options() {
+        let qrcode = crate::preprocessor::Preprocessor::new(
+            "HELLO",
+            crate::encoding::Encoding::Alphanumeric,
+            crate::ec::EcLevel::L,
+            crate::mask::MaskPattern::Checkerboard,
+        )
+        .generate_qrcode();
+
+        assert_eq!(qrcode.render_with(Count).build(), (29 * 8, false));
+        let rendered = qrcode
+            .render_with(Count)
+            .quiet_zone(1)
+            .scale(2)
+            .invert(true)
+            .build();
+        assert_eq!(rendered, (46, true));
+    }
+
+    #[test]
+    fn number_trims_zeros() {
+        assert_eq!(number(2.0), "2");
+        assert_eq!(number(2.5), "2.5");
+        assert_eq!(number(2.834645), "2.835");
+        assert_eq!(number(-0.0001), "0");
+    }
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xfb, 0xff]), "+/8=");
+    }
+}
@@ -0,0 +1,336 @@
+use crate::decode::{decode_luma, luma, Decoded};
+use crate::qrcode::QrCode;
+use crate::render::{dark_at, Color};
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetpbmFormat {
+    /// Bitmap, 1 for dark pixels.
+    Pbm,
+    /// 8-bit grayscale, with the luma of the colours.
+    Pgm,
+    /// 8-bit RGB.
+    Ppm,
+}
+
+pub struct NetpbmOptions {
+    pub format: NetpbmFormat,
+    /// Raw samples (P4, P5, P6) instead of ASCII ones (P1, P2, P3).
+    pub binary: bool,
+    /// Size of a module in pixels.
+    pub scale: u32,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Default for NetpbmOptions {
+    fn default() -> Self {
+        NetpbmOptions {
+            format: NetpbmFormat::Pbm,
+            binary: true,
+            scale: 8,
+            quiet_zone: 4,
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+        }
+    }
+}
+
+/// Renders the symbol as a PBM, PGM or PPM image.
+pub fn render(qrcode: &QrCode, options: &NetpbmOptions) -> Vec<u8> {
+    let scale = options.scale.max(1);
+    let width = (qrcode.size() + 2 * options.quiet_zone) * scale;
+    let dark = |x: u32, y: u32| dark_at(qrcode, options.quiet_zone, x / scale, y / scale);
+    let magic = match (options.format, options.binary) {
+        (NetpbmFormat::Pbm, false) => 1,
+        (NetpbmFormat::Pgm, false) => 2,
+        (NetpbmFormat::Ppm, false) => 3,
+        (NetpbmFormat::Pbm, true) => 4,
+        (NetpbmFormat::Pgm, true) => 5,
+        (NetpbmFormat::Ppm, true) => 6,
+    };
+
+    let mut header = format!("P{}\n{} {}\n", magic, width, width);
+    if options.format != NetpbmFormat::Pbm {
+        header.push_str("255\n");
+    }
+    let mut image = header.into_bytes();
+
+    // samples of a pixel, for every format but binary PBM
+    let samples = |dark: bool| {
+        let color = if dark {
+            options.foreground
+        } else {
+            options.background
+        };
+        match options.format {
+            NetpbmFormat::Pbm => vec![dark as u8],
+            NetpbmFormat::Pgm => vec![luma(color.r, color.g, color.b)],
+            NetpbmFormat::Ppm => vec![color.r, color.g, color.b],
+        }
+    };
+
+    for y in 0..width {
+        if options.binary && options.format == NetpbmFormat::Pbm {
+            // rows are packed 8 pixels per byte, most significant bit first
+            let mut row = vec![0u8; width.div_ceil(8) as usize];
+            for x in (0..width).filter(|&x| dark(x, y)) {
+                row[(x / 8) as usize] |= 0x80 >> (x % 8);
+            }
+            image.extend_from_slice(&row);
+        } else if options.binary {
+            for x in 0..width {
+                image.extend_from_slice(&samples(dark(x, y)));
+            }
+        } else {
+            // ASCII lines should stay under 70 characters, so every pixel gets its own line
+            let mut line = String::new();
+            for x in 0..width {
+                let values: Vec<String> = samples(dark(x, y)).iter().map(u8::to_string).collect();
+                let _ = writeln!(line, "{}", values.join(" "));
+            }
+            image.extend_from_slice(line.as_bytes());
+        }
+    }
+
+    image
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    /// Skips whitespace and `#` comments.
+    fn skip_blank(&mut self) {
+        while let Some(&byte) = self.data.get(self.position) {
+            if byte == b'#' {
+                while self.data.get(self.position).is_some_and(|&b| b != b'\n') {
+                    self.position += 1;
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, String> {
+        self.skip_blank();
+        let start = self.position;
+        while self.data.get(self.position).is_some_and(u8::is_ascii_digit) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.position])
+            .unwrap()
+            .parse()
+            .map_err(|_| "Netpbm number is missing.".to_string())
+    }
+
+    /// An ASCII PBM sample, digits don't need to be separated.
+    fn bit(&mut self) -> Result<u32, String> {
+        self.skip_blank();
+        match self.data.get(self.position) {
+            Some(&digit @ (b'0' | b'1')) => {
+                self.position += 1;
+                Ok((digit - b'0') as u32)
+            }
+            _ => Err("Netpbm bit is missing.".to_string()),
+        }
+    }
+}
+
+/// Reads a PBM, PGM or PPM image as 8-bit luma, returns `(width, height, pixels)`.
+pub fn read(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    if data.len() < 2 || data[0] != b'P' || !(b'1'..=b'6').contains(&data[1]) {
+        return Err("Not a Netpbm image.".to_string());
+    }
+    let magic = data[1] - b'0';
+    let mut parser = Parser { data, position: 2 };
+    let width = parser.number()?;
+    let height = parser.number()?;
+    if width == 0 || height == 0 {
+        return Err("Netpbm size is invalid.".to_string());
+    }
+    let max = if magic == 1 || magic == 4 {
+        1
+    } else {
+        parser.number()?
+    };
+    if max == 0 || max > 65535 {
+        return Err("Netpbm maximum value is out of range.".to_string());
+    }
+    let channels = if magic == 3 || magic == 6 { 3 } else { 1 };
+    let too_large = || "Netpbm size is too large.".to_string();
+    let count = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(too_large)?;
+    let sample_count = count.checked_mul(channels).ok_or_else(too_large)?;
+
+    let samples: Vec<u32> = match magic {
+        1..=3 => {
+            // every ASCII sample takes at least a byte
+            if sample_count > data.len() - parser.position {
+                return Err("Netpbm raster is truncated.".to_string());
+            }
+            if magic == 1 {
+                (0..count).map(|_| parser.bit()).collect::<Result<_, _>>()?
+            } else {
+                (0..sample_count)
+                    .map(|_| parser.number())
+                    .collect::<Result<_, _>>()?
+            }
+        }
+        _ => {
+            // a single whitespace separates the header from the raster
+            let raster = data.get(parser.position + 1..).unwrap_or(&[]);
+            if magic == 4 {
+                let row_bytes = width.div_ceil(8) as usize;
+                let raster_bytes = row_bytes
+                    .checked_mul(height as usize)
+                    .ok_or_else(too_large)?;
+                if raster.len() < raster_bytes {
+                    return Err("Netpbm raster is truncated.".to_string());
+                }
+                (0..count)
+                    .map(|i| {
+                        let (x, y) = (i % width as usize, i / width as usize);
+                        (raster[y * row_bytes + x / 8] >> (7 - x % 8) & 1) as u32
+                    })
+                    .collect()
+            } else {
+                let wide = max > 255;
+                let sample_bytes = if wide { 2 } else { 1 };
+                let raster_bytes = sample_count
+                    .checked_mul(sample_bytes)
+                    .ok_or_else(too_large)?;
+                if raster.len() < raster_bytes {
+                    return Err("Netpbm raster is truncated.".to_string());
+                }
+                raster
+                    .chunks_exact(sample_bytes)
+                    .take(sample_count)
+                    .map(|sample| sample.iter().fold(0, |acc, &b| (acc << 8) | b as u32))
+                    .collect()
+            }
+        }
+    };
+
+    let scale = |sample: u32| (sample.min(max) * 255 / max) as u8;
+    let pixels = match magic {
+        // 1 is black in PBM
+        1 | 4 => samples.iter().map(|&bit| 255 - 255 * bit as u8).collect(),
+        2 | 5 => samples.iter().map(|&sample| scale(sample)).collect(),
+        _ => samples
+            .chunks_exact(3)
+            .map(|rgb| luma(scale(rgb[0]), scale(rgb[1]), scale(rgb[2])))
+            .collect(),
+    };
+    Ok((width, height, pixels))
+}
+
+/// Decodes a symbol from a PBM, PGM or PPM image.
+pub fn decode(data: &[u8]) -> Result<Decoded, String> {
+    let (width, height, pixels) = read(data)?;
+    decode_luma(width, height, &pixels)
+}
+
+impl QrCode {
+    pub fn to_netpbm(&self, options: &NetpbmOptions) -> Vec<u8> {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn render_writes_headers() {
+        let options = NetpbmOptions {
+            scale: 1,
+            quiet_zone: 0,
+            ..Default::default()
+        };
+        let pbm = test_qrcode().to_netpbm(&options);
+        assert!(pbm.starts_with(b"P4\n21 21\n"));
+        // 3 bytes per row, the top row of the finder pattern first
+        assert_eq!(pbm.len(), 9 + 21 * 3);
+        assert_eq!(pbm[9], 0b1111_1110);
+
+        let ascii = test_qrcode().to_netpbm(&NetpbmOptions {
+            format: NetpbmFormat::Ppm,
+            binary: false,
+            ..options
+        });
+        assert!(ascii.starts_with(b"P3\n21 21\n255\n0 0 0\n"));
+    }
+
+    #[test]
+    fn every_format_decodes() {
+        for format in [NetpbmFormat::Pbm, NetpbmFormat::Pgm, NetpbmFormat::Ppm] {
+            for binary in [false, true] {
+                let options = NetpbmOptions {
+                    format,
+                    binary,
+                    scale: 2,
+                    foreground: Color::rgb(0, 0, 128),
+                    ..Default::default()
+                };
+                let image = test_qrcode().to_netpbm(&options);
+                let (width, height, _) = read(&image).unwrap();
+                assert_eq!((width, height), (58, 58));
+                assert_eq!(decode(&image).unwrap().data, b"HELLO WORLD");
+            }
+        }
+    }
+
+    #[test]
+    fn read_handles_comments_and_wide_samples() {
+        let pgm = b"P2\n# a comment\n2 1\n65535\n0 65535\n";
+        assert_eq!(read(pgm).unwrap(), (2, 1, vec![0, 255]));
+        let packed = b"P1 3 1 101";
+        assert_eq!(read(packed).unwrap(), (3, 1, vec![0, 255, 0]));
+        assert!(read(b"P5\n2 2\n255\n\x00").is_err());
+        assert!(read(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn read_rejects_oversized_headers() {
+        let invalid = Err("Netpbm size is invalid.".to_string());
+        assert_eq!(read(b"P5 0 0 255\n"), invalid);
+        assert_eq!(read(b"P4 0 5\n"), invalid);
+        assert!(decode(b"P5 0 0 255\n").is_err());
+        assert!(decode(b"P4 0 5\n").is_err());
+
+        let truncated = Err("Netpbm raster is truncated.".to_string());
+        assert_eq!(read(b"P5 70000 70000 255\n"), truncated);
+        assert_eq!(read(b"P4 70000 70000\n"), truncated);
+        assert_eq!(read(b"P1 70000 70000\n"), truncated);
+        if usize::BITS == 64 {
+            // 2^64 samples
+            assert_eq!(
+                read(b"P6 4294967295 4294967295 65535\n"),
+                Err("Netpbm size is too large.".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn render_clamps_scale_to_one() {
+        let zero = test_qrcode().to_netpbm(&NetpbmOptions {
+            scale: 0,
+            ..Default::default()
+        });
+        let one = test_qrcode().to_netpbm(&NetpbmOptions {
+            scale: 1,
+            ..Default::default()
+        });
+        assert_eq!(zero, one);
+    }
+}
@@ -0,0 +1,528 @@
+use crate::qrcode::QrCode;
+use crate::render::deflate::zlib;
+use crate::render::style::{linear_endpoints, radial_radius, region_shapes, Outline, Paint, Style};
+use crate::render::{dark_runs, number, Color, RenderOptions, Renderer};
+use std::fmt::Write;
+
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+pub struct PdfOptions {
+    /// Page width in millimetres.
+    pub page_width: f64,
+    /// Page height in millimetres.
+    pub page_height: f64,
+    /// Size of a module in millimetres.
+    pub module_size: f64,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub foreground: Color,
+    /// Colour painted behind the symbol and its quiet zone, nothing is painted when `None`.
+    pub background: Option<Color>,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        // A4 portrait
+        PdfOptions {
+            page_width: 210.0,
+            page_height: 297.0,
+            module_size: 1.0,
+            quiet_zone: 4,
+            foreground: Color::BLACK,
+            background: None,
+        }
+    }
+}
+
+/// Renders the symbol centred on a single page.
+pub fn render(qrcode: &QrCode, options: &PdfOptions) -> Result<Vec<u8>, String> {
+    render_pages(std::slice::from_ref(qrcode), options)
+}
+
+/// Renders each symbol centred on its own page. Dark modules are drawn as one filled rectangle
+/// per horizontal run, in an exact physical size.
+pub fn render_pages(qrcodes: &[QrCode], options: &PdfOptions) -> Result<Vec<u8>, String> {
+    if qrcodes.is_empty() {
+        return Err("No symbol to render.".to_string());
+    }
+
+    let module = options.module_size * POINTS_PER_MM;
+    let mut pages = Vec::new();
+    for qrcode in qrcodes {
+        let (left, top, extent) = placement(qrcode, options)?;
+
+        let mut content = String::new();
+        if let Some(background) = options.background {
+            content.push_str(&fill_background(options, extent, background));
+        }
+        content.push_str(&fill_color(options.foreground));
+        for (x, y, length) in dark_runs(qrcode) {
+            let _ = writeln!(
+                content,
+                "{} {} {} {} re",
+                number(left + x as f64 * module),
+                // PDF coordinates start at the bottom of the page
+                number(top - (y + 1) as f64 * module),
+                number(length as f64 * module),
+                number(module)
+            );
+        }
+        content.push_str("f\n");
+        pages.push((content, String::new()));
+    }
+
+    Ok(write_pages(&pages, options))
+}
+
+/// Renders the symbol painted with `style` centred on a single page, the style replaces the
+/// colours of the options. Gradients are axial or radial shadings clipped to their region.
+pub fn render_styled(
+    qrcode: &QrCode,
+    options: &PdfOptions,
+    style: &Style,
+) -> Result<Vec<u8>, String> {
+    let page = styled_page(qrcode, options, style)?;
+    Ok(write_pages(&[page], options))
+}
+
+/// Content stream and resources of the page of `render_styled`.
+fn styled_page(
+    qrcode: &QrCode,
+    options: &PdfOptions,
+    style: &Style,
+) -> Result<(String, String), String> {
+    let (left, top, extent) = placement(qrcode, options)?;
+    let module = options.module_size * POINTS_PER_MM;
+    let size = qrcode.size();
+
+    let mut content = String::new();
+    if let Some(light) = style.light {
+        content.push_str(&fill_background(options, extent, light));
+    }
+    // draw in modules from the top left corner of the symbol, with y growing downwards like SVG
+    let _ = writeln!(
+        content,
+        "q {} 0 0 {} {} {} cm",
+        number(module),
+        number(-module),
+        number(left),
+        number(top)
+    );
+    let mut shadings = String::new();
+    for (i, shapes) in region_shapes(qrcode, style).iter().enumerate() {
+        let paint = style.paint(shapes.region);
+        match paint {
+            Paint::Solid(color) => content.push_str(&fill_color(color)),
+            _ => content.push_str("q\n"),
+        }
+        for (x, y, length) in &shapes.runs {
+            let _ = writeln!(content, "{} {} {} 1 re", x, y, length);
+        }
+        for outline in &shapes.outlines {
+            push_outline(&mut content, outline);
+        }
+
+        let even_odd = if shapes.outlines.is_empty() { "" } else { "*" };
+        let shading = match paint {
+            Paint::Solid(_) => {
+                let _ = writeln!(content, "f{}", even_odd);
+                continue;
+            }
+            Paint::Linear { start, end, angle } => {
+                let ((x1, y1), (x2, y2)) = linear_endpoints(size, angle);
+                let coords = [x1, y1, x2, y2].map(number).join(" ");
+                axial_or_radial(2, &coords, start, end)
+            }
+            Paint::Radial { center, edge } => {
+                let middle = number(size as f64 / 2.0);
+                let coords = format!("{0} {0} 0 {0} {0} {1}", middle, number(radial_radius(size)));
+                axial_or_radial(3, &coords, center, edge)
+            }
+        };
+        let _ = writeln!(content, "W{} n /Sh{} sh Q", even_odd, i);
+        let _ = write!(shadings, "/Sh{} {}", i, shading);
+    }
+    content.push_str("Q\n");
+
+    let resources = if shadings.is_empty() {
+        String::new()
+    } else {
+        format!("/Shading << {} >>", shadings)
+    };
+    Ok((content, resources))
+}
+
+/// Top left corner of the symbol centred on the page, and the side of the symbol with its quiet
+/// zone, in points.
+fn placement(qrcode: &QrCode, options: &PdfOptions) -> Result<(f64, f64, f64), String> {
+    if options.module_size.is_nan() || options.module_size <= 0.0 {
+        return Err(format!(
+            "Module size of {} mm is not positive.",
+            options.module_size
+        ));
+    }
+    let page_width = options.page_width * POINTS_PER_MM;
+    let page_height = options.page_height * POINTS_PER_MM;
+    let module = options.module_size * POINTS_PER_MM;
+
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let extent = modules as f64 * module;
+    if extent > page_width || extent > page_height {
+        return Err(format!(
+            "Symbol of {:.1} mm does not fit on the page.",
+            extent / POINTS_PER_MM
+        ));
+    }
+
+    // centre the symbol
+    let left = (page_width - extent) / 2.0 + options.quiet_zone as f64 * module;
+    let top = (page_height + extent) / 2.0 - options.quiet_zone as f64 * module;
+    Ok((left, top, extent))
+}
+
+/// Fills the centred square of side `extent` with `color`.
+fn fill_background(options: &PdfOptions, extent: f64, color: Color) -> String {
+    let page_width = options.page_width * POINTS_PER_MM;
+    let page_height = options.page_height * POINTS_PER_MM;
+    format!(
+        "{}{} {} {} {} re f\n",
+        fill_color(color),
+        number((page_width - extent) / 2.0),
+        number((page_height - extent) / 2.0),
+        number(extent),
+        number(extent)
+    )
+}
+
+/// Appends a closed subpath along `outline`, with Bézier curves for the rounded corners.
+fn push_outline(content: &mut String, outline: &Outline) {
+    // distance of the control points of a quarter circle from its ends, relative to the radius
+    const KAPPA: f64 = 0.552_284_75;
+    let Outline {
+        left,
+        top,
+        right,
+        bottom,
+        radii: [top_left, top_right, bottom_right, bottom_left],
+    } = *outline;
+
+    let _ = writeln!(content, "{} {} m", number(left + top_left), number(top));
+    // every corner with the directions of the edges before and after it
+    for ((x, y), (in_x, in_y), (out_x, out_y), radius) in [
+        ((right, top), (1.0, 0.0), (0.0, 1.0), top_right),
+        ((right, bottom), (0.0, 1.0), (-1.0, 0.0), bottom_right),
+        ((left, bottom), (-1.0, 0.0), (0.0, -1.0), bottom_left),
+        ((left, top), (0.0, -1.0), (1.0, 0.0), top_left),
+    ] {
+        let point = |dx: f64, dy: f64| format!("{} {}", number(x + dx), number(y + dy));
+        if radius > 0.0 {
+            let handle = radius * (1.0 - KAPPA);
+            let _ = writeln!(
+                content,
+                "{} l {} {} {} c",
+                point(-in_x * radius, -in_y * radius),
+                point(-in_x * handle, -in_y * handle),
+                point(out_x * handle, out_y * handle),
+                point(out_x * radius, out_y * radius)
+            );
+        } else {
+            let _ = writeln!(content, "{} l", point(0.0, 0.0));
+        }
+    }
+    content.push_str("h\n");
+}
+
+/// Shading dictionary of `shading_type` 2 (axial) or 3 (radial) from `from` to `to`.
+fn axial_or_radial(shading_type: u8, coords: &str, from: Color, to: Color) -> String {
+    format!(
+        "<< /ShadingType {} /ColorSpace /DeviceRGB /Coords [{}] /Function << /FunctionType 2 \
+         /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >> /Extend [true true] >>",
+        shading_type,
+        coords,
+        rgb(from),
+        rgb(to)
+    )
+}
+
+/// Writes a document with a page per `(content, resources)`.
+fn write_pages(pages: &[(String, String)], options: &PdfOptions) -> Vec<u8> {
+    let page_width = options.page_width * POINTS_PER_MM;
+    let page_height = options.page_height * POINTS_PER_MM;
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string().into_bytes(),
+        Vec::new(),
+    ];
+    let mut kids = Vec::new();
+
+    for (content, resources) in pages {
+        let compressed = zlib(content.as_bytes());
+        let mut stream = format!(
+            "<< /Length {} /Filter /FlateDecode >>\nstream\n",
+            compressed.len()
+        )
+        .into_bytes();
+        stream.extend_from_slice(&compressed);
+        stream.extend_from_slice(b"\nendstream");
+
+        let page_id = objects.len() + 1;
+        kids.push(format!("{} 0 R", page_id));
+        // a page must have a resource dictionary, even an empty one
+        let resources = if resources.is_empty() {
+            "<< >>".to_string()
+        } else {
+            format!("<< {} >>", resources)
+        };
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources {} \
+                 /Contents {} 0 R >>",
+                number(page_width),
+                number(page_height),
+                resources,
+                page_id + 1
+            )
+            .into_bytes(),
+        );
+        objects.push(stream);
+    }
+
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        kids.len()
+    )
+    .into_bytes();
+
+    write_document(&objects)
+}
+
+fn rgb(color: Color) -> String {
+    format!(
+        "{} {} {}",
+        number(color.r as f64 / 255.0),
+        number(color.g as f64 / 255.0),
+        number(color.b as f64 / 255.0)
+    )
+}
+
+fn fill_color(color: Color) -> String {
+    format!("{} rg\n", rgb(color))
+}
+
+fn write_document(objects: &[Vec<u8>]) -> Vec<u8> {
+    // the binary comment tells tools the file is not plain text
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.extend_from_slice(table.as_bytes());
+    pdf
+}
+
+/// `Renderer` for PDF, the scale is the module size in points and the light colour is painted
+/// behind the symbol.
+pub struct Pdf {
+    /// Page width in millimetres.
+    pub page_width: f64,
+    /// Page height in millimetres.
+    pub page_height: f64,
+}
+
+impl Default for Pdf {
+    fn default() -> Self {
+        let options = PdfOptions::default();
+        Pdf {
+            page_width: options.page_width,
+            page_height: options.page_height,
+        }
+    }
+}
+
+impl Renderer for Pdf {
+    type Output = Result<Vec<u8>, String>;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Result<Vec<u8>, String> {
+        let (foreground, background) = options.colors();
+        let pdf_options = PdfOptions {
+            page_width: self.page_width,
+            page_height: self.page_height,
+            module_size: options.scale as f64 / POINTS_PER_MM,
+            quiet_zone: options.quiet_zone,
+            foreground,
+            background: Some(background),
+        };
+        render(qrcode, &pdf_options)
+    }
+}
+
+/// `Renderer` for `render_styled`, the scale is the module size in points and the style replaces
+/// the colours and inversion of the options.
+pub struct StyledPdf {
+    pub style: Style,
+    /// Page width in millimetres.
+    pub page_width: f64,
+    /// Page height in millimetres.
+    pub page_height: f64,
+}
+
+impl Default for StyledPdf {
+    fn default() -> Self {
+        let options = PdfOptions::default();
+        StyledPdf {
+            style: Style::default(),
+            page_width: options.page_width,
+            page_height: options.page_height,
+        }
+    }
+}
+
+impl Renderer for StyledPdf {
+    type Output = Result<Vec<u8>, String>;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Result<Vec<u8>, String> {
+        let pdf_options = PdfOptions {
+            page_width: self.page_width,
+            page_height: self.page_height,
+            module_size: options.scale as f64 / POINTS_PER_MM,
+            quiet_zone: options.quiet_zone,
+            ..PdfOptions::default()
+        };
+        render_styled(qrcode, &pdf_options, &self.style)
+    }
+}
+
+impl QrCode {
+    pub fn to_pdf(&self, options: &PdfOptions) -> Result<Vec<u8>, String> {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::EcLevel;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskPattern;
+    use crate::preprocessor::Preprocessor;
+    use crate::render::style::EyeShape;
+
+    fn qrcode(data: &str) -> QrCode {
+        Preprocessor::new(data, Encoding::Byte, EcLevel::M, MaskPattern::Diagonal).generate_qrcode()
+    }
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    #[test]
+    fn render_writes_valid_cross_reference_table() {
+        let pdf = qrcode("https://example.com")
+            .to_pdf(&PdfOptions::default())
+            .unwrap();
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+
+        let text = String::from_utf8_lossy(&pdf);
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(&pdf[startxref..startxref + 4], b"xref");
+
+        // every object offset must point at its "n 0 obj" line
+        let table = &text[text.find("xref\n").unwrap()..];
+        for (i, line) in table.lines().skip(3).take(4).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            let header = format!("{} 0 obj", i + 1);
+            assert_eq!(&pdf[offset..offset + header.len()], header.as_bytes());
+        }
+        assert!(find(&pdf, b"/MediaBox [0 0 595.276 841.89] /Resources << >>").is_some());
+    }
+
+    #[test]
+    fn render_pages_writes_one_page_per_symbol() {
+        let qrcodes = [qrcode("first"), qrcode("second"), qrcode("third")];
+        let pdf = render_pages(&qrcodes, &PdfOptions::default()).unwrap();
+        assert!(find(&pdf, b"/Kids [3 0 R 5 0 R 7 0 R] /Count 3").is_some());
+    }
+
+    #[test]
+    fn render_returns_error_when_symbol_does_not_fit() {
+        let options = PdfOptions {
+            page_width: 20.0,
+            page_height: 20.0,
+            ..PdfOptions::default()
+        };
+        assert!(qrcode("https://example.com").to_pdf(&options).is_err());
+        assert!(render_pages(&[], &options).is_err());
+
+        for module_size in [0.0, -1.0, f64::NAN] {
+            let options = PdfOptions {
+                module_size,
+                ..PdfOptions::default()
+            };
+            assert!(qrcode("https://example.com").to_pdf(&options).is_err());
+        }
+    }
+
+    #[test]
+    fn render_styled_clips_shadings_to_regions() {
+        let style = Style {
+            dark: Paint::Radial {
+                center: Color::rgb(255, 0, 0),
+                edge: Color::rgb(0, 0, 255),
+            },
+            light: None,
+            eye_frame: Some(Paint::Solid(Color::rgb(0, 128, 0))),
+            eye_frame_shape: EyeShape::Rounded,
+            ..Default::default()
+        };
+        let qrcode = qrcode("HELLO");
+        let (content, resources) = styled_page(&qrcode, &PdfOptions::default(), &style).unwrap();
+        assert!(!content.contains(" re f\n"));
+        // modules of 1 mm, from the top left corner of the symbol
+        assert!(content.starts_with("q 2.835 0 0 -2.835 267.874 450.709 cm\n"));
+        assert!(content.contains("W n /Sh0 sh Q\n"));
+        assert!(resources.starts_with(
+            "/Shading << /Sh0 << /ShadingType 3 /ColorSpace /DeviceRGB \
+             /Coords [10.5 10.5 0 10.5 10.5 14.849]"
+        ));
+        // the outer edge of the top left frame, filled even-odd with its hole
+        assert!(content.contains("0 0.502 0 rg\n2 0 m\n5 0 l 6.105 0 7 0.895 7 2 c\n"));
+        assert!(content.ends_with("h\nf*\nQ\n"));
+
+        let pdf = render_styled(&qrcode, &PdfOptions::default(), &style).unwrap();
+        assert!(find(&pdf, b"/Resources << /Shading << /Sh0").is_some());
+    }
+
+    #[test]
+    fn renderer_scale_is_in_points() {
+        let qrcode = qrcode("HELLO");
+        assert!(qrcode.render::<Pdf>().scale(20).build().is_ok());
+        // 29 modules of 21 points are wider than A4
+        assert!(qrcode.render::<Pdf>().scale(21).build().is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::qrcode::QrCode;
+use crate::render::deflate::zlib;
+use crate::render::style::Style;
+use crate::render::{Color, RenderOptions, Renderer};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+pub struct PngOptions {
+    /// Size of a module in pixels.
+    pub scale: u32,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        PngOptions {
+            scale: 8,
+            quiet_zone: 4,
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+        }
+    }
+}
+
+/// Renders the symbol as a 1-bit PNG image, grayscale for black on white and indexed otherwise.
+pub fn render(qrcode: &QrCode, options: &PngOptions) -> Vec<u8> {
+    let scale = options.scale.max(1);
+    let size = qrcode.size();
+    let modules = size + 2 * options.quiet_zone;
+    let width = modules * scale;
+    let row_bytes = width.div_ceil(8) as usize;
+
+    // each scanline starts with its filter type, 0 for none
+    let mut scanlines = Vec::with_capacity((row_bytes + 1) * width as usize);
+    let mut row = vec![0u8; row_bytes];
+    for my in 0..modules {
+        // white is 1 in grayscale and the background is palette entry 1, so light modules and
+        // padding bits are set
+        row.fill(0xFF);
+        if my >= options.quiet_zone && my < size + options.quiet_zone {
+            for x in 0..size {
+                if qrcode.get(x, my - options.quiet_zone).unwrap().value() {
+                    let start = (x + options.quiet_zone) * scale;
+                    for px in start..start + scale {
+                        row[(px / 8) as usize] &= !(0x80 >> (px % 8));
+                    }
+                }
+            }
+        }
+        for _ in 0..scale {
+            scanlines.push(0);
+            scanlines.extend_from_slice(&row);
+        }
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&width.to_be_bytes());
+    let grayscale = options.foreground == Color::BLACK && options.background == Color::WHITE;
+    // bit depth 1, grayscale or indexed, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[1, if grayscale { 0 } else { 3 }, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    if !grayscale {
+        let (fg, bg) = (options.foreground, options.background);
+        write_chunk(&mut png, b"PLTE", &[fg.r, fg.g, fg.b, bg.r, bg.g, bg.b]);
+    }
+    write_chunk(&mut png, b"IDAT", &zlib(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Encodes 8-bit RGBA pixels, row by row, as a PNG image.
+pub(crate) fn encode_rgba(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let row_bytes = width as usize * 4;
+    let mut scanlines = Vec::with_capacity((row_bytes + 1) * height as usize);
+    for row in pixels.chunks(row_bytes) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // bit depth 8, truecolour with alpha, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// `Renderer` for PNG, the scale is the module size in pixels.
+#[derive(Default)]
+pub struct Png;
+
+impl Renderer for Png {
+    type Output = Vec<u8>;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Vec<u8> {
+        let (foreground, background) = options.colors();
+        let png_options = PngOptions {
+            scale: options.scale,
+            quiet_zone: options.quiet_zone,
+            foreground,
+            background,
+        };
+        render(qrcode, &png_options)
+    }
+}
+
+/// `Renderer` for an RGBA PNG painted with a `Style`, which replaces the colours and inversion of
+/// the options.
+#[derive(Default)]
+pub struct StyledPng {
+    pub style: Style,
+}
+
+impl Renderer for StyledPng {
+    type Output = Vec<u8>;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Vec<u8> {
+        let (width, pixels) = self
+            .style
+            .rgba_pixels(qrcode, options.scale, options.quiet_zone);
+        encode_rgba(width, width, &pixels)
+    }
+}
+
+impl QrCode {
+    pub fn to_png(&self, options: &PngOptions) -> Vec<u8> {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn crc32_works() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn render_writes_png_chunks() {
+        let png = test_qrcode().to_png(&PngOptions {
+            scale: 3,
+            quiet_zone: 2,
+            ..Default::default()
+        });
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        // (21 + 2 * 2) * 3 pixels wide and high
+        assert_eq!(png[16..20], 75u32.to_be_bytes());
+        assert_eq!(png[20..24], 75u32.to_be_bytes());
+        assert_eq!(png[24..29], [1, 0, 0, 0, 0]);
+        assert_eq!(&png[37..41], b"IDAT");
+        assert_eq!(
+            png[png.len() - 12..],
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn render_writes_palette_for_colours() {
+        let png = test_qrcode().to_png(&PngOptions {
+            foreground: Color::rgb(0, 0, 128),
+            ..Default::default()
+        });
+        assert_eq!(png[24..29], [1, 3, 0, 0, 0]);
+        assert_eq!(&png[37..41], b"PLTE");
+        assert_eq!(png[41..47], [0, 0, 128, 255, 255, 255]);
+    }
+
+    #[test]
+    fn styled_png_is_rgba() {
+        let png = test_qrcode()
+            .render_with(StyledPng::default())
+            .scale(2)
+            .build();
+        assert_eq!(png[16..20], 58u32.to_be_bytes());
+        assert_eq!(png[24..29], [8, 6, 0, 0, 0]);
+    }
+
+    #[test]
+    fn render_clamps_scale_to_one() {
+        let zero = test_qrcode().to_png(&PngOptions {
+            scale: 0,
+            ..Default::default()
+        });
+        let one = test_qrcode().to_png(&PngOptions {
+            scale: 1,
+            ..Default::default()
+        });
+        assert_eq!(zero, one);
+
+        let styled = test_qrcode()
+            .render_with(StyledPng::default())
+            .scale(0)
+            .build();
+        // 21 + 2 * 4 pixels wide
+        assert_eq!(styled[16..20], 29u32.to_be_bytes());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::qrcode::QrCode;
+use crate::render::{number, RenderOptions};
+
+const MM_PER_INCH: f64 = 25.4;
+/// Quiet zone required by the specification, in modules.
+pub const MIN_QUIET_ZONE: u32 = 4;
+
+/// What to do when the printed modules are smaller than the minimum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinimumPolicy {
+    /// Report it in `PrintSize::warning`.
+    Warn,
+    /// Fail with the same message.
+    Fail,
+}
+
+pub struct PrintOptions {
+    /// Largest side of the printed symbol, quiet zone included, in millimetres.
+    pub size_mm: f64,
+    /// Printer resolution in dots per inch.
+    pub dpi: u32,
+    /// Smallest module the printer and scanners can be trusted with, in millimetres.
+    pub min_module_mm: f64,
+    /// Width of the quiet zone in modules, raised to `MIN_QUIET_ZONE`.
+    pub quiet_zone: u32,
+    pub policy: MinimumPolicy,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            size_mm: 25.0,
+            dpi: 300,
+            min_module_mm: 0.25,
+            quiet_zone: MIN_QUIET_ZONE,
+            policy: MinimumPolicy::Warn,
+        }
+    }
+}
+
+/// Pixel and physical dimensions of a symbol at a given resolution.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintSize {
+    /// Printer dots per module.
+    pub scale: u32,
+    pub quiet_zone: u32,
+    /// Side of the image in dots, quiet zone included.
+    pub pixels: u32,
+    /// Printed size of a module, in millimetres.
+    pub module_mm: f64,
+    /// Printed side of the symbol, quiet zone included, in millimetres.
+    pub size_mm: f64,
+    /// Set when the modules are smaller than `PrintOptions::min_module_mm`.
+    pub warning: Option<String>,
+}
+
+impl PrintSize {
+    /// Options to render the symbol at this size, with the default colours.
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            scale: self.scale,
+            quiet_zone: self.quiet_zone,
+            ..Default::default()
+        }
+    }
+}
+
+/// Largest whole number of dots per module that fits the symbol and its quiet zone in
+/// `options.size_mm` at `options.dpi`.
+pub fn print_size(qrcode: &QrCode, options: &PrintOptions) -> Result<PrintSize, String> {
+    if options.dpi == 0 || options.size_mm <= 0.0 {
+        return Err("Size and resolution must be positive.".to_string());
+    }
+    let quiet_zone = options.quiet_zone.max(MIN_QUIET_ZONE);
+    let modules = qrcode.size() + 2 * quiet_zone;
+    let dots = (options.size_mm / MM_PER_INCH * options.dpi as f64).floor() as u32;
+    let scale = dots / modules;
+    if scale == 0 {
+        return Err(format!(
+            "{} modules don't fit in {} mm at {} DPI.",
+            modules,
+            number(options.size_mm),
+            options.dpi
+        ));
+    }
+
+    let dot_mm = MM_PER_INCH / options.dpi as f64;
+    let module_mm = scale as f64 * dot_mm;
+    let warning = (module_mm < options.min_module_mm).then(|| {
+        format!(
+            "Modules of version {} are {} mm, below the minimum of {} mm.",
+            qrcode.version(),
+            number(module_mm),
+            number(options.min_module_mm)
+        )
+    });
+    if let (Some(warning), MinimumPolicy::Fail) = (&warning, options.policy) {
+        return Err(warning.clone());
+    }
+
+    Ok(PrintSize {
+        scale,
+        quiet_zone,
+        pixels: scale * modules,
+        module_mm,
+        size_mm: (scale * modules) as f64 * dot_mm,
+        warning,
+    })
+}
+
+impl QrCode {
+    pub fn print_size(&self, options: &PrintOptions) -> Result<PrintSize, String> {
+        print_size(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn print_size_picks_the_largest_whole_scale() {
+        let options = PrintOptions {
+            size_mm: 20.0,
+            quiet_zone: 1,
+            ..Default::default()
+        };
+        // 236 dots for 29 modules with the quiet zone raised to 4
+        let size = test_qrcode().print_size(&options).unwrap();
+        assert_eq!((size.scale, size.quiet_zone, size.pixels), (8, 4, 232));
+        assert_eq!(number(size.module_mm), "0.677");
+        assert_eq!(number(size.size_mm), "19.643");
+        assert_eq!(size.warning, None);
+        assert_eq!(size.render_options().scale, 8);
+    }
+
+    #[test]
+    fn small_modules_warn_or_fail() {
+        let mut options = PrintOptions {
+            size_mm: 10.0,
+            dpi: 600,
+            min_module_mm: 0.5,
+            ..Default::default()
+        };
+        let message = "Modules of version 1 are 0.339 mm, below the minimum of 0.5 mm.";
+        let size = test_qrcode().print_size(&options).unwrap();
+        assert_eq!(size.warning.as_deref(), Some(message));
+
+        options.policy = MinimumPolicy::Fail;
+        assert_eq!(test_qrcode().print_size(&options), Err(message.to_string()));
+    }
+
+    #[test]
+    fn print_size_fails_below_a_dot_per_module() {
+        let options = PrintOptions {
+            size_mm: 10.0,
+            dpi: 72,
+            ..Default::default()
+        };
+        assert_eq!(
+            test_qrcode().print_size(&options),
+            Err("29 modules don't fit in 10 mm at 72 DPI.".to_string())
+        );
+    }
+}
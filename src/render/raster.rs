@@ -0,0 +1,130 @@
+use crate::qrcode::QrCode;
+use crate::render::{dark_at, RenderOptions, Renderer};
+
+/// 8-bit grayscale pixels of the symbol, `options.scale` pixels per module, as
+/// `(width, height, pixels)`. The dark and light colours are converted to luma.
+pub fn luma8(qrcode: &QrCode, options: &RenderOptions) -> (u32, u32, Vec<u8>) {
+    let (dark, light) = options.colors();
+    let (dark, light) = (
+        crate::decode::luma(dark.r, dark.g, dark.b),
+        crate::decode::luma(light.r, light.g, light.b),
+    );
+    pixels(qrcode, options, |is_dark| {
+        [if is_dark { dark } else { light }]
+    })
+}
+
+/// 8-bit RGBA pixels of the symbol, `options.scale` pixels per module, as
+/// `(width, height, pixels)`. Every pixel is opaque.
+pub fn rgba8(qrcode: &QrCode, options: &RenderOptions) -> (u32, u32, Vec<u8>) {
+    let (dark, light) = options.colors();
+    pixels(qrcode, options, |is_dark| {
+        let color = if is_dark { dark } else { light };
+        [color.r, color.g, color.b, 255]
+    })
+}
+
+fn pixels<const N: usize>(
+    qrcode: &QrCode,
+    options: &RenderOptions,
+    pixel: impl Fn(bool) -> [u8; N],
+) -> (u32, u32, Vec<u8>) {
+    let scale = options.scale.max(1);
+    let width = (qrcode.size() + 2 * options.quiet_zone) * scale;
+    let mut pixels = Vec::with_capacity((width * width) as usize * N);
+    for y in 0..width {
+        for x in 0..width {
+            let is_dark = dark_at(qrcode, options.quiet_zone, x / scale, y / scale);
+            pixels.extend_from_slice(&pixel(is_dark));
+        }
+    }
+    (width, width, pixels)
+}
+
+/// Renders grayscale pixels with [`luma8`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Luma8;
+
+impl Renderer for Luma8 {
+    type Output = (u32, u32, Vec<u8>);
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Self::Output {
+        luma8(qrcode, options)
+    }
+}
+
+/// Renders RGBA pixels with [`rgba8`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rgba8;
+
+impl Renderer for Rgba8 {
+    type Output = (u32, u32, Vec<u8>);
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> Self::Output {
+        rgba8(qrcode, options)
+    }
+}
+
+impl QrCode {
+    /// Black on white grayscale pixels as `(width, height, pixels)`.
+    pub fn to_luma8(&self, scale: u32, quiet_zone: u32) -> (u32, u32, Vec<u8>) {
+        luma8(
+            self,
+            &RenderOptions {
+                scale,
+                quiet_zone,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Black on white RGBA pixels as `(width, height, pixels)`.
+    pub fn to_rgba8(&self, scale: u32, quiet_zone: u32) -> (u32, u32, Vec<u8>) {
+        rgba8(
+            self,
+            &RenderOptions {
+                scale,
+                quiet_zone,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::{decode_luma, decode_rgba};
+    use crate::render::test_qrcode;
+    use crate::render::Color;
+
+    #[test]
+    fn to_luma8_scales_modules_and_quiet_zone() {
+        let (width, height, pixels) = test_qrcode().to_luma8(2, 1);
+        assert_eq!((width, height), (46, 46));
+        assert_eq!(pixels.len(), 46 * 46);
+        // quiet zone, then the corner of the top left finder pattern
+        assert_eq!(&pixels[2 * 46..2 * 46 + 4], &[255, 255, 0, 0]);
+        assert_eq!(
+            decode_luma(width, height, &pixels).unwrap().data,
+            b"HELLO WORLD"
+        );
+    }
+
+    #[test]
+    fn rgba8_uses_the_render_options() {
+        let (width, height, pixels) = test_qrcode()
+            .render::<Rgba8>()
+            .scale(3)
+            .dark_color(Color::rgb(0, 0, 128))
+            .build();
+        assert_eq!((width, height), (87, 87));
+        let corner = 4 * (12 * 87 + 12);
+        assert_eq!(&pixels[corner..corner + 4], &[0, 0, 128, 255]);
+        assert_eq!(&pixels[..4], &[255, 255, 255, 255]);
+        assert_eq!(
+            decode_rgba(width, height, &pixels).unwrap().data,
+            b"HELLO WORLD"
+        );
+    }
+}
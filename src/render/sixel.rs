@@ -0,0 +1,163 @@
+use crate::qrcode::QrCode;
+use crate::render::{dark_at, Color, RenderOptions, Renderer};
+use std::fmt::Write;
+
+pub struct SixelOptions {
+    /// Size of a module in pixels.
+    pub scale: u32,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub dark: Color,
+    pub light: Color,
+}
+
+impl Default for SixelOptions {
+    fn default() -> Self {
+        SixelOptions {
+            scale: 4,
+            quiet_zone: 4,
+            dark: Color::BLACK,
+            light: Color::WHITE,
+        }
+    }
+}
+
+/// Sixel colour components are in percent.
+fn percent(component: u8) -> u32 {
+    (component as u32 * 100 + 127) / 255
+}
+
+/// Appends `length` copies of a sixel, with a repeat introducer when that's shorter.
+fn push_run(sixels: &mut String, sixel: char, length: u32) {
+    if length > 3 {
+        let _ = write!(sixels, "!{}{}", length, sixel);
+    } else {
+        (0..length).for_each(|_| sixels.push(sixel));
+    }
+}
+
+/// Renders the symbol as a Sixel image with two colour registers, light modules are 0 and dark
+/// ones are 1. Every band of 6 pixel rows draws the light then the dark pixels.
+pub fn render(qrcode: &QrCode, options: &SixelOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let scale = options.scale.max(1);
+    let pixels = modules * scale;
+    let mut sixels = String::new();
+
+    // 1:1 pixel aspect ratio, and the raster size so terminals can reserve the space up front
+    let _ = write!(sixels, "\x1bP0;1;0q\"1;1;{};{}", pixels, pixels);
+    for (register, color) in [options.light, options.dark].iter().enumerate() {
+        let _ = write!(
+            sixels,
+            "#{};2;{};{};{}",
+            register,
+            percent(color.r),
+            percent(color.g),
+            percent(color.b)
+        );
+    }
+
+    for band in (0..pixels).step_by(6) {
+        for (register, dark) in [(0, false), (1, true)] {
+            if dark {
+                sixels.push('$');
+            }
+            let _ = write!(sixels, "#{}", register);
+
+            let mut run: Option<(char, u32)> = None;
+            for x in 0..pixels {
+                let bits = (0..6)
+                    .filter(|&row| band + row < pixels)
+                    .filter(|&row| {
+                        let (mx, my) = (x / scale, (band + row) / scale);
+                        dark_at(qrcode, options.quiet_zone, mx, my) == dark
+                    })
+                    .fold(0, |bits, row| bits | 1 << row);
+                let sixel = char::from(63 + bits as u8);
+
+                run = match run {
+                    Some((previous, length)) if previous == sixel => Some((sixel, length + 1)),
+                    Some((previous, length)) => {
+                        push_run(&mut sixels, previous, length);
+                        Some((sixel, 1))
+                    }
+                    None => Some((sixel, 1)),
+                };
+            }
+            if let Some((sixel, length)) = run {
+                push_run(&mut sixels, sixel, length);
+            }
+        }
+        sixels.push('-');
+    }
+
+    sixels.push_str("\x1b\\");
+    sixels
+}
+
+/// `Renderer` for Sixel, the scale is the module size in pixels.
+#[derive(Default)]
+pub struct Sixel;
+
+impl Renderer for Sixel {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        let (dark, light) = options.colors();
+        let sixel_options = SixelOptions {
+            scale: options.scale,
+            quiet_zone: options.quiet_zone,
+            dark,
+            light,
+        };
+        render(qrcode, &sixel_options)
+    }
+}
+
+impl QrCode {
+    pub fn to_sixel(&self, options: &SixelOptions) -> String {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn render_writes_header_and_colour_registers() {
+        let sixels = render(&test_qrcode(), &SixelOptions::default());
+        assert!(sixels.starts_with("\x1bP0;1;0q\"1;1;116;116#0;2;100;100;100#1;2;0;0;0"));
+        assert!(sixels.ends_with("-\x1b\\"));
+        // ceil(116 / 6) bands
+        assert_eq!(sixels.matches('-').count(), 20);
+
+        // a zero scale draws one pixel per module
+        let unscaled = |scale| SixelOptions {
+            scale,
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&test_qrcode(), &unscaled(0)),
+            render(&test_qrcode(), &unscaled(1))
+        );
+    }
+
+    #[test]
+    fn render_draws_modules_at_scale() {
+        let options = SixelOptions {
+            scale: 6,
+            quiet_zone: 0,
+            ..Default::default()
+        };
+        let sixels = render(&test_qrcode(), &options);
+        let bands: Vec<&str> = sixels.split('-').collect();
+        // the first band is the top row of modules, so every sixel is full or empty
+        let first = bands[0].split("#0").nth(2).unwrap();
+        let (light, dark) = first.split_once("$#1").unwrap();
+        // finder pattern, separator, then data
+        assert!(light.starts_with("!42?!6~"));
+        assert!(dark.starts_with("!42~!6?"));
+    }
+}
@@ -0,0 +1,598 @@
+use crate::bit::ModuleRole;
+use crate::decode::decode_rgba;
+use crate::qrcode::QrCode;
+use crate::render::Color;
+
+/// How dark modules are painted. Gradients span the symbol without its quiet zone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Paint {
+    Solid(Color),
+    /// Gradient from `start` to `end`, `angle` in degrees clockwise from left to right.
+    Linear {
+        start: Color,
+        end: Color,
+        angle: f64,
+    },
+    /// Gradient from `center` in the middle of the symbol to `edge` in its corners.
+    Radial {
+        center: Color,
+        edge: Color,
+    },
+}
+
+impl Paint {
+    /// Colour at `(x, y)`, in modules from the top left corner of a symbol of `size` modules.
+    pub(crate) fn color_at(&self, size: u32, x: f64, y: f64) -> Color {
+        match *self {
+            Paint::Solid(color) => color,
+            Paint::Linear { start, end, angle } => {
+                let ((x1, y1), (x2, y2)) = linear_endpoints(size, angle);
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                let t = ((x - x1) * dx + (y - y1) * dy) / (dx * dx + dy * dy);
+                mix(start, end, t)
+            }
+            Paint::Radial { center, edge } => {
+                let middle = size as f64 / 2.0;
+                let t = (x - middle).hypot(y - middle) / radial_radius(size);
+                mix(center, edge, t)
+            }
+        }
+    }
+}
+
+/// Ends of a linear gradient at `angle` so that it just covers a symbol of `size` modules.
+pub(crate) fn linear_endpoints(size: u32, angle: f64) -> ((f64, f64), (f64, f64)) {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let middle = size as f64 / 2.0;
+    let half_length = (cos.abs() + sin.abs()) * middle;
+    (
+        (middle - cos * half_length, middle - sin * half_length),
+        (middle + cos * half_length, middle + sin * half_length),
+    )
+}
+
+/// Radius of a radial gradient reaching the corners of a symbol of `size` modules.
+pub(crate) fn radial_radius(size: u32) -> f64 {
+    size as f64 / std::f64::consts::SQRT_2
+}
+
+fn mix(from: Color, to: Color, t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let component = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Color::rgb(
+        component(from.r, to.r),
+        component(from.g, to.g),
+        component(from.b, to.b),
+    )
+}
+
+/// Parts of the symbol that can be painted separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    Data,
+    /// Outer ring of a finder pattern.
+    EyeFrame,
+    /// 3×3 centre of a finder pattern.
+    EyeBall,
+    Alignment,
+}
+
+impl Region {
+    pub const ALL: [Region; 4] = [
+        Region::Data,
+        Region::EyeFrame,
+        Region::EyeBall,
+        Region::Alignment,
+    ];
+}
+
+/// Shape of dark modules outside the finder patterns. Every shape stays inside its module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleShape {
+    Square,
+    Circle,
+    /// Squares with rounded corners.
+    Rounded,
+    /// Squares whose corners are rounded unless a neighbour, diagonal included, shares them, so
+    /// adjacent modules merge into blobs.
+    Connected,
+}
+
+/// Shape of the frame or the ball of the finder patterns, drawn within the 7×7 pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EyeShape {
+    Square,
+    Rounded,
+    Circle,
+}
+
+impl EyeShape {
+    /// Corner radii of the outer edge and the hole of the frame, and of the ball.
+    fn radii(self) -> (f64, f64, f64) {
+        match self {
+            EyeShape::Square => (0.0, 0.0, 0.0),
+            EyeShape::Rounded => (2.0, 1.0, 1.0),
+            EyeShape::Circle => (3.5, 2.5, 1.5),
+        }
+    }
+}
+
+/// Rectangle with rounded corners, in modules. Radii are top left, top right, bottom right and
+/// bottom left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Outline {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub radii: [f64; 4],
+}
+
+impl Outline {
+    fn square(left: f64, top: f64, width: f64, radius: f64) -> Outline {
+        Outline {
+            left,
+            top,
+            right: left + width,
+            bottom: top + width,
+            radii: [radius; 4],
+        }
+    }
+
+    pub(crate) fn contains(&self, x: f64, y: f64) -> bool {
+        if x < self.left || x > self.right || y < self.top || y > self.bottom {
+            return false;
+        }
+        let (center_x, center_y) = (
+            (self.left + self.right) / 2.0,
+            (self.top + self.bottom) / 2.0,
+        );
+        let radius = match (x < center_x, y < center_y) {
+            (true, true) => self.radii[0],
+            (false, true) => self.radii[1],
+            (false, false) => self.radii[2],
+            (true, false) => self.radii[3],
+        };
+        // distance past the straight part of the edges, towards the corner
+        let dx = (x - center_x).abs() - ((self.right - self.left) / 2.0 - radius);
+        let dy = (y - center_y).abs() - ((self.bottom - self.top) / 2.0 - radius);
+        dx <= 0.0 || dy <= 0.0 || dx * dx + dy * dy <= radius * radius
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Style {
+    pub dark: Paint,
+    /// Background of the symbol and its quiet zone, transparent when `None`.
+    pub light: Option<Color>,
+    /// Paints of the finder patterns and alignment patterns, `dark` is used when `None`.
+    pub eye_frame: Option<Paint>,
+    pub eye_ball: Option<Paint>,
+    pub alignment: Option<Paint>,
+    pub module_shape: ModuleShape,
+    pub eye_frame_shape: EyeShape,
+    pub eye_ball_shape: EyeShape,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            dark: Paint::Solid(Color::BLACK),
+            light: Some(Color::WHITE),
+            eye_frame: None,
+            eye_ball: None,
+            alignment: None,
+            module_shape: ModuleShape::Square,
+            eye_frame_shape: EyeShape::Square,
+            eye_ball_shape: EyeShape::Square,
+        }
+    }
+}
+
+impl Style {
+    /// Region the dark module at `(x, y)` is painted as. Regions without their own paint are
+    /// painted as data.
+    pub(crate) fn region(&self, qrcode: &QrCode, x: u32, y: u32) -> Region {
+        let region = part(qrcode, x, y);
+        if self.region_paint(region).is_some() {
+            region
+        } else {
+            Region::Data
+        }
+    }
+
+    fn region_paint(&self, region: Region) -> Option<Paint> {
+        match region {
+            Region::Data => Some(self.dark),
+            Region::EyeFrame => self.eye_frame,
+            Region::EyeBall => self.eye_ball,
+            Region::Alignment => self.alignment,
+        }
+    }
+
+    pub(crate) fn paint(&self, region: Region) -> Paint {
+        self.region_paint(region).unwrap_or(self.dark)
+    }
+
+    /// Whether the dark module at `(x, y)` is drawn as a plain square.
+    pub(crate) fn is_square(&self, qrcode: &QrCode, x: u32, y: u32) -> bool {
+        match part(qrcode, x, y) {
+            Region::EyeFrame => self.eye_frame_shape == EyeShape::Square,
+            Region::EyeBall => self.eye_ball_shape == EyeShape::Square,
+            _ => self.module_shape == ModuleShape::Square,
+        }
+    }
+
+    /// Whether everything is drawn as squares, so edges can be rendered crisp.
+    pub(crate) fn is_all_square(&self) -> bool {
+        self.module_shape == ModuleShape::Square
+            && self.eye_frame_shape == EyeShape::Square
+            && self.eye_ball_shape == EyeShape::Square
+    }
+
+    /// Outline of the dark module at `(x, y)` outside the finder patterns.
+    pub(crate) fn module_outline(&self, qrcode: &QrCode, x: u32, y: u32) -> Outline {
+        let (left, top) = (x as f64, y as f64);
+        let radii = match self.module_shape {
+            ModuleShape::Square => [0.0; 4],
+            ModuleShape::Circle => [0.5; 4],
+            ModuleShape::Rounded => [0.25; 4],
+            ModuleShape::Connected => {
+                let dark = |dx: i64, dy: i64| {
+                    let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                    nx >= 0
+                        && ny >= 0
+                        && qrcode
+                            .get(nx as u32, ny as u32)
+                            .is_some_and(|bit| bit.value())
+                };
+                // a corner is rounded when none of the 3 modules sharing it is dark
+                let corner = |dx: i64, dy: i64| {
+                    if dark(dx, 0) || dark(0, dy) || dark(dx, dy) {
+                        0.0
+                    } else {
+                        0.5
+                    }
+                };
+                [corner(-1, -1), corner(1, -1), corner(1, 1), corner(-1, 1)]
+            }
+        };
+        Outline {
+            left,
+            top,
+            right: left + 1.0,
+            bottom: top + 1.0,
+            radii,
+        }
+    }
+
+    /// Outer edge and hole of the frame, and the ball, of the finder pattern whose top left
+    /// corner is `(left, top)`.
+    pub(crate) fn eye_outlines(&self, left: u32, top: u32) -> (Outline, Outline, Outline) {
+        let (left, top) = (left as f64, top as f64);
+        let (outer, hole, _) = self.eye_frame_shape.radii();
+        let (_, _, ball) = self.eye_ball_shape.radii();
+        (
+            Outline::square(left, top, 7.0, outer),
+            Outline::square(left + 1.0, top + 1.0, 5.0, hole),
+            Outline::square(left + 2.0, top + 2.0, 3.0, ball),
+        )
+    }
+
+    /// Renders the symbol and decodes the image, to make sure the shapes and colours still scan.
+    /// SVG, PDF and EPS output have the same geometry, so this covers them too.
+    pub fn check(&self, qrcode: &QrCode, scale: u32, quiet_zone: u32) -> Result<(), String> {
+        let expected = qrcode.decode()?.data;
+        let (width, pixels) = self.rgba_pixels(qrcode, scale, quiet_zone);
+        let decoded = decode_rgba(width, width, &pixels)
+            .map_err(|err| format!("Rendered symbol does not decode: {}", err))?;
+        if decoded.data != expected {
+            return Err("Rendered symbol decodes to different data.".to_string());
+        }
+        Ok(())
+    }
+
+    /// RGBA pixels of the symbol with its quiet zone, `scale` pixels per module (at least 1), and
+    /// the width of the image.
+    pub(crate) fn rgba_pixels(
+        &self,
+        qrcode: &QrCode,
+        scale: u32,
+        quiet_zone: u32,
+    ) -> (u32, Vec<u8>) {
+        let scale = scale.max(1);
+        let size = qrcode.size();
+        let width = (size + 2 * quiet_zone) * scale;
+        let light = self.light.map_or([0; 4], |c| [c.r, c.g, c.b, 255]);
+        let mut pixels = Vec::with_capacity((width * width * 4) as usize);
+
+        for py in 0..width {
+            for px in 0..width {
+                let (mx, my) = (px / scale, py / scale);
+                let module = match (mx.checked_sub(quiet_zone), my.checked_sub(quiet_zone)) {
+                    (Some(x), Some(y)) if x < size && y < size => Some((x, y)),
+                    _ => None,
+                };
+                // paint at the centre of the pixel
+                let fx = (px as f64 + 0.5) / scale as f64 - quiet_zone as f64;
+                let fy = (py as f64 + 0.5) / scale as f64 - quiet_zone as f64;
+                let painted = module.and_then(|(x, y)| self.painted_as(qrcode, x, y, fx, fy));
+                match painted {
+                    Some(region) => {
+                        let color = self.paint(region).color_at(size, fx, fy);
+                        pixels.extend_from_slice(&[color.r, color.g, color.b, 255]);
+                    }
+                    None => pixels.extend_from_slice(&light),
+                }
+            }
+        }
+
+        (width, pixels)
+    }
+}
+
+impl Style {
+    /// Region painted at `(fx, fy)` inside the module at `(x, y)`, `None` when it is light.
+    fn painted_as(&self, qrcode: &QrCode, x: u32, y: u32, fx: f64, fy: f64) -> Option<Region> {
+        if qrcode.role(x, y) == Some(ModuleRole::Finder) {
+            let last = qrcode.size() - 7;
+            let (left, top) = (if x < 7 { 0 } else { last }, if y < 7 { 0 } else { last });
+            let (outer, hole, ball) = self.eye_outlines(left, top);
+            return if ball.contains(fx, fy) {
+                Some(self.region(qrcode, left + 3, top + 3))
+            } else if outer.contains(fx, fy) && !hole.contains(fx, fy) {
+                Some(self.region(qrcode, left, top))
+            } else {
+                None
+            };
+        }
+
+        let dark = qrcode.get(x, y).is_some_and(|bit| bit.value());
+        (dark && self.module_outline(qrcode, x, y).contains(fx, fy))
+            .then(|| self.region(qrcode, x, y))
+    }
+}
+
+/// Part of the symbol the module at `(x, y)` belongs to.
+fn part(qrcode: &QrCode, x: u32, y: u32) -> Region {
+    match qrcode.role(x, y) {
+        Some(ModuleRole::Finder) => {
+            // finder patterns are centred 3 modules in from their corner
+            let last = qrcode.size() - 4;
+            let center_x = if x < 7 { 3 } else { last };
+            let center_y = if y < 7 { 3 } else { last };
+            if x.abs_diff(center_x) <= 1 && y.abs_diff(center_y) <= 1 {
+                Region::EyeBall
+            } else {
+                Region::EyeFrame
+            }
+        }
+        Some(ModuleRole::Alignment) => Region::Alignment,
+        _ => Region::Data,
+    }
+}
+
+/// Top left corners of the 3 finder patterns.
+pub(crate) fn finder_corners(qrcode: &QrCode) -> [(u32, u32); 3] {
+    let last = qrcode.size() - 7;
+    [(0, 0), (last, 0), (0, last)]
+}
+
+/// Horizontal runs of dark modules drawn as squares and painted as `region`, as
+/// `(x, y, length)`.
+pub(crate) fn region_runs(qrcode: &QrCode, style: &Style, region: Region) -> Vec<(u32, u32, u32)> {
+    let size = qrcode.size();
+    let mut runs = Vec::new();
+
+    for y in 0..size {
+        let mut start = None;
+        for x in 0..=size {
+            let inside = x < size
+                && qrcode.get(x, y).unwrap().value()
+                && style.is_square(qrcode, x, y)
+                && style.region(qrcode, x, y) == region;
+            match (inside, start) {
+                (true, None) => start = Some(x),
+                (false, Some(begin)) => {
+                    runs.push((begin, y, x - begin));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    runs
+}
+
+/// Dark modules of a region, as horizontal runs of squares and outlines of the other shapes.
+pub(crate) struct RegionShapes {
+    pub region: Region,
+    pub runs: Vec<(u32, u32, u32)>,
+    pub outlines: Vec<Outline>,
+}
+
+/// Shapes of every region with dark modules. The eye frames are an outer edge and a hole, so
+/// regions with outlines are filled even-odd.
+pub(crate) fn region_shapes(qrcode: &QrCode, style: &Style) -> Vec<RegionShapes> {
+    let size = qrcode.size();
+    let mut outlines: Vec<(Region, Outline)> = Vec::new();
+    for (left, top) in finder_corners(qrcode) {
+        let (outer, hole, ball) = style.eye_outlines(left, top);
+        if !style.is_square(qrcode, left, top) {
+            let region = style.region(qrcode, left, top);
+            outlines.push((region, outer));
+            outlines.push((region, hole));
+        }
+        if !style.is_square(qrcode, left + 3, top + 3) {
+            outlines.push((style.region(qrcode, left + 3, top + 3), ball));
+        }
+    }
+    for y in 0..size {
+        for x in 0..size {
+            if qrcode.get(x, y).unwrap().value()
+                && qrcode.role(x, y) != Some(ModuleRole::Finder)
+                && !style.is_square(qrcode, x, y)
+            {
+                outlines.push((
+                    style.region(qrcode, x, y),
+                    style.module_outline(qrcode, x, y),
+                ));
+            }
+        }
+    }
+
+    Region::ALL
+        .iter()
+        .map(|&region| RegionShapes {
+            region,
+            runs: region_runs(qrcode, style, region),
+            outlines: outlines
+                .iter()
+                .filter(|(outline_region, _)| *outline_region == region)
+                .map(|(_, outline)| *outline)
+                .collect(),
+        })
+        .filter(|shapes| !shapes.runs.is_empty() || !shapes.outlines.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::EcLevel;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskPattern;
+    use crate::preprocessor::Preprocessor;
+
+    fn qrcode() -> QrCode {
+        Preprocessor::new(
+            "https://example.com",
+            Encoding::Byte,
+            EcLevel::M,
+            MaskPattern::Checkerboard,
+        )
+        .generate_qrcode()
+    }
+
+    #[test]
+    fn gradients_span_the_symbol() {
+        let red = Color::rgb(255, 0, 0);
+        let blue = Color::rgb(0, 0, 255);
+        let linear = Paint::Linear {
+            start: red,
+            end: blue,
+            angle: 0.0,
+        };
+        assert_eq!(linear.color_at(20, 0.0, 7.0), red);
+        assert_eq!(linear.color_at(20, 10.0, 0.0), Color::rgb(128, 0, 128));
+        assert_eq!(linear.color_at(20, 25.0, 3.0), blue);
+
+        let diagonal = Paint::Linear {
+            start: red,
+            end: blue,
+            angle: 45.0,
+        };
+        assert_eq!(diagonal.color_at(20, 0.0, 0.0), red);
+        assert_eq!(diagonal.color_at(20, 20.0, 20.0), blue);
+
+        let radial = Paint::Radial {
+            center: red,
+            edge: blue,
+        };
+        assert_eq!(radial.color_at(20, 10.0, 10.0), red);
+        assert_eq!(radial.color_at(20, 0.0, 20.0), blue);
+    }
+
+    #[test]
+    fn region_follows_module_roles() {
+        let qrcode = qrcode();
+        let last = qrcode.size() - 1;
+        let style = Style {
+            eye_frame: Some(Paint::Solid(Color::BLACK)),
+            eye_ball: Some(Paint::Solid(Color::BLACK)),
+            alignment: Some(Paint::Solid(Color::BLACK)),
+            ..Default::default()
+        };
+
+        assert_eq!(style.region(&qrcode, 0, 0), Region::EyeFrame);
+        assert_eq!(style.region(&qrcode, 4, 2), Region::EyeBall);
+        assert_eq!(style.region(&qrcode, last - 2, 4), Region::EyeBall);
+        assert_eq!(style.region(&qrcode, 2, last), Region::EyeFrame);
+        // the alignment pattern of version 2 is centred on (18, 18)
+        assert_eq!(style.region(&qrcode, 16, 16), Region::Alignment);
+        assert_eq!(style.region(&qrcode, 10, 10), Region::Data);
+
+        // without their own paint, eyes are painted like data
+        assert_eq!(Style::default().region(&qrcode, 0, 0), Region::Data);
+    }
+
+    #[test]
+    fn rgba_pixels_are_transparent_without_background() {
+        let style = Style {
+            light: None,
+            ..Default::default()
+        };
+        let (width, pixels) = style.rgba_pixels(&qrcode(), 2, 1);
+        assert_eq!(width, 54);
+        assert_eq!(pixels.len(), 54 * 54 * 4);
+        assert_eq!(pixels[..4], [0, 0, 0, 0]);
+        // first pixel of the finder pattern
+        let offset = ((2 * 54 + 2) * 4) as usize;
+        assert_eq!(pixels[offset..offset + 4], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn outlines_round_their_corners() {
+        let circle = Outline::square(0.0, 0.0, 1.0, 0.5);
+        assert!(circle.contains(0.5, 0.5));
+        assert!(circle.contains(0.5, 0.02));
+        assert!(!circle.contains(0.05, 0.05));
+        assert!(!circle.contains(1.2, 0.5));
+
+        let style = Style {
+            module_shape: ModuleShape::Connected,
+            ..Default::default()
+        };
+        // function patterns only, so data modules are all light
+        let mut template =
+            QrCode::new(2, EcLevel::M, MaskPattern::Checkerboard, Encoding::Byte).unwrap();
+        template.all_functional_patterns();
+        // a module of the timing pattern has no dark neighbour
+        assert_eq!(style.module_outline(&template, 8, 6).radii, [0.5; 4]);
+        // the top left corner of the alignment pattern centred on (18, 18)
+        assert_eq!(
+            style.module_outline(&template, 16, 16).radii,
+            [0.5, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn check_decodes_shaped_symbols() {
+        let qrcode = qrcode();
+        for (module_shape, eye_shape) in [
+            (ModuleShape::Square, EyeShape::Square),
+            (ModuleShape::Circle, EyeShape::Circle),
+            (ModuleShape::Rounded, EyeShape::Rounded),
+            (ModuleShape::Connected, EyeShape::Circle),
+        ] {
+            let style = Style {
+                module_shape,
+                eye_frame_shape: eye_shape,
+                eye_ball_shape: eye_shape,
+                ..Default::default()
+            };
+            assert_eq!(style.check(&qrcode, 8, 4), Ok(()));
+        }
+
+        let faint = Style {
+            dark: Paint::Solid(Color::rgb(250, 250, 250)),
+            light: Some(Color::rgb(240, 240, 240)),
+            ..Default::default()
+        };
+        assert!(faint.check(&qrcode, 8, 4).is_err());
+
+        // a scale of 0 is drawn at 1 pixel per module
+        assert_eq!(Style::default().check(&qrcode, 0, 4), Ok(()));
+    }
+}
@@ -0,0 +1,359 @@
+use crate::qrcode::QrCode;
+use crate::render::style::{linear_endpoints, radial_radius, region_shapes, Outline, Paint, Style};
+use crate::render::{dark_runs, number, Color, RenderOptions, Renderer};
+use std::fmt::Write;
+
+pub struct SvgOptions {
+    /// Size of a module in user units (pixels when displayed as is).
+    pub module_size: u32,
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    pub foreground: Color,
+    pub background: Color,
+    pub title: Option<String>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            module_size: 8,
+            quiet_zone: 4,
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+            title: None,
+        }
+    }
+}
+
+/// Renders the symbol as an SVG document. All dark modules are merged in a single path made of
+/// horizontal runs, and coordinates are in modules so the path stays short.
+pub fn render(qrcode: &QrCode, options: &SvgOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let pixels = modules * options.module_size;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{0}\" height=\"{0}\" \
+         viewBox=\"0 0 {1} {1}\" shape-rendering=\"crispEdges\">",
+        pixels, modules
+    );
+    if let Some(title) = &options.title {
+        let _ = write!(svg, "<title>{}</title>", escape(title));
+    }
+    let _ = write!(
+        svg,
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        modules,
+        modules,
+        options.background.to_hex()
+    );
+
+    let _ = write!(svg, "<path fill=\"{}\" d=\"", options.foreground.to_hex());
+    for (x, y, length) in dark_runs(qrcode) {
+        let _ = write!(
+            svg,
+            "M{} {}h{}v1h-{}z",
+            x + options.quiet_zone,
+            y + options.quiet_zone,
+            length,
+            length
+        );
+    }
+    svg.push_str("\"/></svg>\n");
+
+    svg
+}
+
+/// Renders the symbol painted with `style`, with a path per region and gradients in user space
+/// so they span the symbol whatever the region.
+pub fn render_styled(
+    qrcode: &QrCode,
+    module_size: u32,
+    quiet_zone: u32,
+    title: Option<&str>,
+    style: &Style,
+) -> String {
+    let size = qrcode.size();
+    let modules = size + 2 * quiet_zone;
+    let offset = quiet_zone as f64;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{0}\" height=\"{0}\" \
+         viewBox=\"0 0 {1} {1}\"{2}>",
+        modules * module_size,
+        modules,
+        if style.is_all_square() {
+            " shape-rendering=\"crispEdges\""
+        } else {
+            ""
+        }
+    );
+    if let Some(title) = title {
+        let _ = write!(svg, "<title>{}</title>", escape(title));
+    }
+    if let Some(light) = style.light {
+        let _ = write!(
+            svg,
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            modules,
+            modules,
+            light.to_hex()
+        );
+    }
+
+    for (i, shapes) in region_shapes(qrcode, style).iter().enumerate() {
+        let fill = match style.paint(shapes.region) {
+            Paint::Solid(color) => color.to_hex(),
+            Paint::Linear { start, end, angle } => {
+                let ((x1, y1), (x2, y2)) = linear_endpoints(size, angle);
+                let _ = write!(
+                    svg,
+                    "<linearGradient id=\"qr{}\" gradientUnits=\"userSpaceOnUse\" \
+                     x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}</linearGradient>",
+                    i,
+                    number(x1 + offset),
+                    number(y1 + offset),
+                    number(x2 + offset),
+                    number(y2 + offset),
+                    stops(start, end)
+                );
+                format!("url(#qr{})", i)
+            }
+            Paint::Radial { center, edge } => {
+                let middle = size as f64 / 2.0 + offset;
+                let _ = write!(
+                    svg,
+                    "<radialGradient id=\"qr{}\" gradientUnits=\"userSpaceOnUse\" \
+                     cx=\"{}\" cy=\"{}\" r=\"{}\">{}</radialGradient>",
+                    i,
+                    number(middle),
+                    number(middle),
+                    number(radial_radius(size)),
+                    stops(center, edge)
+                );
+                format!("url(#qr{})", i)
+            }
+        };
+
+        if shapes.outlines.is_empty() {
+            let _ = write!(svg, "<path fill=\"{}\" d=\"", fill);
+        } else {
+            let _ = write!(svg, "<path fill=\"{}\" fill-rule=\"evenodd\" d=\"", fill);
+        }
+        for (x, y, length) in &shapes.runs {
+            let _ = write!(
+                svg,
+                "M{} {}h{}v1h-{}z",
+                x + quiet_zone,
+                y + quiet_zone,
+                length,
+                length
+            );
+        }
+        for outline in &shapes.outlines {
+            push_outline(&mut svg, outline, offset);
+        }
+        svg.push_str("\"/>");
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Appends a closed subpath along `outline`, moved by `offset` modules.
+pub(crate) fn push_outline(svg: &mut String, outline: &Outline, offset: f64) {
+    let (left, top) = (outline.left + offset, outline.top + offset);
+    let (right, bottom) = (outline.right + offset, outline.bottom + offset);
+    let [top_left, top_right, bottom_right, bottom_left] = outline.radii;
+
+    let arc = |svg: &mut String, radius: f64, x: f64, y: f64| {
+        if radius > 0.0 {
+            let r = number(radius);
+            let _ = write!(svg, "A{} {} 0 0 1 {} {}", r, r, number(x), number(y));
+        }
+    };
+    let _ = write!(svg, "M{} {}", number(left + top_left), number(top));
+    let _ = write!(svg, "H{}", number(right - top_right));
+    arc(svg, top_right, right, top + top_right);
+    let _ = write!(svg, "V{}", number(bottom - bottom_right));
+    arc(svg, bottom_right, right - bottom_right, bottom);
+    let _ = write!(svg, "H{}", number(left + bottom_left));
+    arc(svg, bottom_left, left, bottom - bottom_left);
+    let _ = write!(svg, "V{}", number(top + top_left));
+    arc(svg, top_left, left + top_left, top);
+    svg.push('z');
+}
+
+fn stops(start: Color, end: Color) -> String {
+    format!(
+        "<stop offset=\"0\" stop-color=\"{}\"/><stop offset=\"1\" stop-color=\"{}\"/>",
+        start.to_hex(),
+        end.to_hex()
+    )
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `Renderer` for SVG, the scale is the module size in user units.
+#[derive(Default)]
+pub struct Svg {
+    pub title: Option<String>,
+}
+
+impl Renderer for Svg {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        let (foreground, background) = options.colors();
+        let svg_options = SvgOptions {
+            module_size: options.scale,
+            quiet_zone: options.quiet_zone,
+            foreground,
+            background,
+            title: self.title.clone(),
+        };
+        render(qrcode, &svg_options)
+    }
+}
+
+/// `Renderer` for `render_styled`, the style replaces the colours and inversion of the options.
+#[derive(Default)]
+pub struct StyledSvg {
+    pub style: Style,
+    pub title: Option<String>,
+}
+
+impl Renderer for StyledSvg {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        render_styled(
+            qrcode,
+            options.scale,
+            options.quiet_zone,
+            self.title.as_deref(),
+            &self.style,
+        )
+    }
+}
+
+impl QrCode {
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        render(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::style::{EyeShape, ModuleShape};
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn render_merges_dark_modules_in_one_path() {
+        let svg = test_qrcode().to_svg(&SvgOptions::default());
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("width=\"232\" height=\"232\" viewBox=\"0 0 29 29\""));
+        assert_eq!(svg.matches("<path").count(), 1);
+        // top row of the top left finder pattern
+        assert!(svg.contains("d=\"M4 4h7v1h-7z"));
+        assert!(svg.ends_with("\"/></svg>\n"));
+    }
+
+    #[test]
+    fn render_is_deterministic() {
+        let options = SvgOptions::default();
+        assert_eq!(
+            test_qrcode().to_svg(&options),
+            test_qrcode().to_svg(&options)
+        );
+    }
+
+    #[test]
+    fn render_applies_options() {
+        let options = SvgOptions {
+            module_size: 2,
+            quiet_zone: 0,
+            foreground: Color::rgb(0x12, 0x34, 0x56),
+            background: Color::rgb(0xff, 0xee, 0xdd),
+            title: Some("Tom & \"Jerry\" <3".to_string()),
+        };
+        let svg = test_qrcode().to_svg(&options);
+        assert!(svg.contains("width=\"42\" height=\"42\" viewBox=\"0 0 21 21\""));
+        assert!(svg.contains("<title>Tom &amp; &quot;Jerry&quot; &lt;3</title>"));
+        assert!(svg.contains("fill=\"#ffeedd\""));
+        assert!(svg.contains("<path fill=\"#123456\" d=\"M0 0h7v1h-7z"));
+    }
+
+    #[test]
+    fn renderer_swaps_colours_when_inverted() {
+        let svg = test_qrcode()
+            .render::<Svg>()
+            .scale(2)
+            .quiet_zone(0)
+            .invert(true)
+            .build();
+        assert!(svg.contains("width=\"42\" height=\"42\""));
+        assert!(svg.contains("<path fill=\"#ffffff\""));
+    }
+
+    #[test]
+    fn render_styled_paints_regions_and_gradients() {
+        let style = Style {
+            dark: Paint::Linear {
+                start: Color::rgb(255, 0, 0),
+                end: Color::rgb(0, 0, 255),
+                angle: 90.0,
+            },
+            light: None,
+            eye_frame: Some(Paint::Solid(Color::rgb(0x12, 0x34, 0x56))),
+            ..Default::default()
+        };
+        let svg = render_styled(&test_qrcode(), 4, 2, None, &style);
+        assert!(!svg.contains("<rect"));
+        assert!(svg.contains(
+            "<linearGradient id=\"qr0\" gradientUnits=\"userSpaceOnUse\" \
+             x1=\"12.5\" y1=\"2\" x2=\"12.5\" y2=\"23\">"
+        ));
+        assert!(svg.contains("<path fill=\"url(#qr0)\" d=\""));
+        // the eye frame path starts with the top row of the top left finder pattern
+        assert!(svg.contains("<path fill=\"#123456\" d=\"M2 2h7v1h-7z"));
+        assert_eq!(svg.matches("<path").count(), 2);
+    }
+
+    #[test]
+    fn render_styled_draws_shapes() {
+        let style = Style {
+            module_shape: ModuleShape::Circle,
+            eye_frame_shape: EyeShape::Rounded,
+            ..Default::default()
+        };
+        let svg = render_styled(&test_qrcode(), 8, 4, None, &style);
+        assert!(!svg.contains("crispEdges"));
+        assert!(svg.contains("fill-rule=\"evenodd\""));
+        // the outer edge of the top left frame, then its hole
+        assert!(
+            svg.contains("M6 4H9A2 2 0 0 1 11 6V9A2 2 0 0 1 9 11H6A2 2 0 0 1 4 9V6A2 2 0 0 1 6 4z")
+        );
+        assert!(svg.contains("M6 5H9A1 1 0 0 1 10 6"));
+        assert!(svg.contains("A0.5 0.5 0 0 1"));
+        // the square balls are still runs
+        assert!(svg.contains("M6 6h3v1h-3z"));
+    }
+}
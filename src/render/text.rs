@@ -0,0 +1,274 @@
+use crate::qrcode::QrCode;
+use crate::render::{dark_at, RenderOptions, Renderer};
+
+/// Quadrant glyphs indexed by their top left, top right, bottom left and bottom right
+/// quarters, from the most significant bit.
+const QUADRANTS: [char; 16] = [
+    ' ', '▗', '▖', '▄', '▝', '▐', '▞', '▟', '▘', '▚', '▌', '▙', '▀', '▜', '▛', '█',
+];
+
+pub struct TextOptions {
+    /// Width of the quiet zone, in modules.
+    pub quiet_zone: u32,
+    /// See `RenderOptions::invert`, which also gives the default.
+    pub invert: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self::from(&RenderOptions::default())
+    }
+}
+
+impl From<&RenderOptions> for TextOptions {
+    fn from(options: &RenderOptions) -> Self {
+        TextOptions {
+            quiet_zone: options.quiet_zone,
+            invert: options.invert,
+        }
+    }
+}
+
+/// Whether the glyph covering `(x, y)` is filled there, nothing is filled past the quiet zone.
+fn filled(qrcode: &QrCode, options: &TextOptions, x: u32, y: u32) -> bool {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    x < modules && y < modules && dark_at(qrcode, options.quiet_zone, x, y) != options.invert
+}
+
+/// Draws every module as two characters, `██` or two spaces, which is what `Display` uses.
+pub fn blocks(qrcode: &QrCode, options: &TextOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let mut text = String::new();
+
+    for y in 0..modules {
+        for x in 0..modules {
+            text.push_str(if filled(qrcode, options, x, y) {
+                "██"
+            } else {
+                "  "
+            });
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Draws 2 modules per character with `▀▄█` half blocks, one line for every 2 rows.
+pub fn half_blocks(qrcode: &QrCode, options: &TextOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let mut text = String::new();
+
+    for y in (0..modules).step_by(2) {
+        for x in 0..modules {
+            let top = filled(qrcode, options, x, y);
+            let bottom = filled(qrcode, options, x, y + 1);
+            text.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Draws 2×2 modules per character with quadrant blocks, about a quarter of `half_blocks` width.
+/// Square modules rely on characters being twice as high as wide.
+pub fn quadrant_blocks(qrcode: &QrCode, options: &TextOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let mut text = String::new();
+
+    for y in (0..modules).step_by(2) {
+        for x in (0..modules).step_by(2) {
+            let index = (filled(qrcode, options, x, y) as usize) << 3
+                | (filled(qrcode, options, x + 1, y) as usize) << 2
+                | (filled(qrcode, options, x, y + 1) as usize) << 1
+                | filled(qrcode, options, x + 1, y + 1) as usize;
+            text.push(QUADRANTS[index]);
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Braille dot bits of a 2×4 cell, indexed by row then column.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Draws 2×4 modules per character with raised Braille dots. Dots don't touch, so this is a
+/// preview for logs and chats rather than something to scan.
+pub fn braille(qrcode: &QrCode, options: &TextOptions) -> String {
+    let modules = qrcode.size() + 2 * options.quiet_zone;
+    let mut text = String::new();
+
+    for y in (0..modules).step_by(4) {
+        for x in (0..modules).step_by(2) {
+            let mut dots = 0;
+            for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, dot) in row.iter().enumerate() {
+                    if filled(qrcode, options, x + dx as u32, y + dy as u32) {
+                        dots |= dot;
+                    }
+                }
+            }
+            text.push(char::from_u32(0x2800 + dots).unwrap());
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// `Renderer` for `blocks`, text renderers ignore the scale and colours.
+#[derive(Default)]
+pub struct Blocks;
+
+impl Renderer for Blocks {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        blocks(qrcode, &options.into())
+    }
+}
+
+/// `Renderer` for `half_blocks`.
+#[derive(Default)]
+pub struct HalfBlocks;
+
+impl Renderer for HalfBlocks {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        half_blocks(qrcode, &options.into())
+    }
+}
+
+/// `Renderer` for `quadrant_blocks`.
+#[derive(Default)]
+pub struct QuadrantBlocks;
+
+impl Renderer for QuadrantBlocks {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        quadrant_blocks(qrcode, &options.into())
+    }
+}
+
+/// `Renderer` for `braille`.
+#[derive(Default)]
+pub struct Braille;
+
+impl Renderer for Braille {
+    type Output = String;
+
+    fn render(&self, qrcode: &QrCode, options: &RenderOptions) -> String {
+        braille(qrcode, &options.into())
+    }
+}
+
+impl QrCode {
+    pub fn to_half_blocks(&self, options: &TextOptions) -> String {
+        half_blocks(self, options)
+    }
+
+    pub fn to_quadrant_blocks(&self, options: &TextOptions) -> String {
+        quadrant_blocks(self, options)
+    }
+
+    pub fn to_braille(&self, options: &TextOptions) -> String {
+        braille(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::test_qrcode;
+
+    #[test]
+    fn half_blocks_uses_one_line_per_two_rows() {
+        let options = TextOptions {
+            quiet_zone: 0,
+            invert: false,
+        };
+        let text = half_blocks(&test_qrcode(), &options);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert!(lines.iter().all(|line| line.chars().count() == 21));
+        // rows 0 and 1 of the top left finder pattern, then its separator
+        assert!(lines[0].starts_with("█▀▀▀▀▀█ "));
+        // the last line only has row 20
+        assert!(lines[10].starts_with("▀▀▀▀▀▀▀ "));
+        assert!(!text.contains("Version"));
+    }
+
+    #[test]
+    fn half_blocks_inverts_and_adds_quiet_zone() {
+        let options = TextOptions {
+            invert: true,
+            ..Default::default()
+        };
+        let text = half_blocks(&test_qrcode(), &options);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 15);
+        assert_eq!(lines[0], "█".repeat(29));
+        assert!(lines[2].starts_with("████ ▄▄▄▄▄ █"));
+    }
+
+    #[test]
+    fn quadrant_blocks_packs_four_modules_per_character() {
+        let options = TextOptions {
+            quiet_zone: 1,
+            invert: false,
+        };
+        let text = quadrant_blocks(&test_qrcode(), &options);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 12);
+        assert!(lines.iter().all(|line| line.chars().count() == 12));
+        // quiet zone above and left of the finder pattern corner
+        assert!(lines[0].starts_with('▗'));
+        assert!(lines[0][3..].starts_with('▄'));
+    }
+
+    #[test]
+    fn braille_packs_eight_modules_per_character() {
+        let options = TextOptions {
+            quiet_zone: 0,
+            invert: false,
+        };
+        let text = braille(&test_qrcode(), &options);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines.iter().all(|line| line.chars().count() == 11));
+        // first 4 rows of the top left finder pattern and its separator
+        assert!(lines[0].starts_with("⡏⣭⡍⡇"));
+
+        let inverted = TextOptions {
+            invert: true,
+            ..Default::default()
+        };
+        assert!(braille(&test_qrcode(), &inverted).starts_with("⣿⣿⣿"));
+        // not inverted by default, like the other renderers
+        assert!(braille(&test_qrcode(), &TextOptions::default()).starts_with("⠀⠀"));
+    }
+
+    #[test]
+    fn renderers_take_shared_options() {
+        let qrcode = test_qrcode();
+        let text = qrcode.render::<HalfBlocks>().quiet_zone(0).build();
+        assert!(text.starts_with("█▀▀▀▀▀█ "));
+
+        let blocks = qrcode.render::<Blocks>().quiet_zone(1).invert(true).build();
+        assert!(blocks.starts_with(&"██".repeat(23)));
+        assert!(blocks
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("██              ██"));
+    }
+}
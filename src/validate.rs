@@ -0,0 +1,308 @@
+use crate::ec::{data_codewords_count, deinterleave, ec_codewords_per_block, syndromes, EcLevel};
+use crate::encoding::Encoding;
+use crate::qrcode::QrCode;
+
+/// Generator polynomial of the format information BCH code.
+pub(crate) const FORMAT_GENERATOR: u32 = 0x537;
+/// Mask XORed with the format information, so it is never all light.
+pub(crate) const FORMAT_MASK: u32 = 0x5412;
+/// Generator polynomial of the version information BCH code.
+const VERSION_GENERATOR: u32 = 0x1F25;
+
+impl QrCode {
+    /// Checks a finished symbol against the structural rules: function patterns, format and
+    /// version information, dark module, and data that decodes without any RS error.
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_finder_patterns()?;
+        self.validate_timing_patterns()?;
+        self.validate_alignment_patterns()?;
+        self.validate_format_information()?;
+        self.validate_version_information()?;
+        if !self.dark(8, 4 * self.version() as u32 + 9) {
+            return Err("Dark module is not set.".to_string());
+        }
+        self.validate_codewords()
+    }
+
+    fn dark(&self, x: u32, y: u32) -> bool {
+        self.get(x, y).is_some_and(|bit| bit.value())
+    }
+
+    fn validate_finder_patterns(&self) -> Result<(), String> {
+        let size = self.size() as i64;
+
+        for (x, y) in [(0, 0), (size - 7, 0), (0, size - 7)] {
+            // the separator is the ring of light modules around the pattern
+            for dy in -1..8 {
+                for dx in -1..8 {
+                    let (mx, my) = (x + dx, y + dy);
+                    if mx < 0 || my < 0 || mx >= size || my >= size {
+                        continue;
+                    }
+
+                    // 0 for the outer border, 1 for the light ring, 2 or more for the center
+                    let ring = dx.min(dy).min(6 - dx).min(6 - dy);
+                    let expected = ring == 0 || ring >= 2;
+                    if self.dark(mx as u32, my as u32) != expected {
+                        let part = if ring < 0 {
+                            "Separator"
+                        } else {
+                            "Finder pattern"
+                        };
+                        return Err(format!("{} at ({}, {}) is damaged.", part, x, y));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_timing_patterns(&self) -> Result<(), String> {
+        for i in 8..self.size() - 8 {
+            if self.dark(i, 6) != (i % 2 == 0) || self.dark(6, i) != (i % 2 == 0) {
+                return Err("Timing pattern is damaged.".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_alignment_patterns(&self) -> Result<(), String> {
+        for (x, y) in Self::alignment_pattern_centers(self.version()) {
+            for dy in 0..5 {
+                for dx in 0..5 {
+                    let ring = dx.min(dy).min(4 - dx).min(4 - dy);
+                    if self.dark(x + dx - 2, y + dy - 2) != (ring != 1) {
+                        return Err(format!("Alignment pattern at ({}, {}) is damaged.", x, y));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_format_information(&self) -> Result<(), String> {
+        let size = self.size();
+        // positions of the format bits, most significant bit first
+        let mut first = (0..9)
+            .filter(|&x| x != 6)
+            .map(|x| (x, 8))
+            .chain((0..8).rev().filter(|&y| y != 6).map(|y| (8, y)));
+        let mut second = ((size - 7)..size)
+            .rev()
+            .map(|y| (8, y))
+            .chain(((size - 8)..size).map(|x| (x, 8)));
+
+        let read = |positions: &mut dyn Iterator<Item = (u32, u32)>| {
+            positions.fold(0, |acc, (x, y)| (acc << 1) | self.dark(x, y) as u32)
+        };
+        let format = read(&mut first);
+        if format != read(&mut second) {
+            return Err("Format information copies differ.".to_string());
+        }
+
+        let unmasked = format ^ FORMAT_MASK;
+        if bch_remainder(unmasked, FORMAT_GENERATOR) != 0 {
+            return Err("Format information is not a valid BCH codeword.".to_string());
+        }
+
+        let ec_level = match unmasked >> 13 {
+            0b01 => EcLevel::L,
+            0b00 => EcLevel::M,
+            0b11 => EcLevel::Q,
+            _ => EcLevel::H,
+        };
+        let mask = (unmasked >> 10) & 0b111;
+        if ec_level.ordinal() != self.ec_level().ordinal()
+            || mask != self.mask_pattern().ordinal() as u32
+        {
+            return Err("Format information does not match the symbol.".to_string());
+        }
+        Ok(())
+    }
+
+    fn validate_version_information(&self) -> Result<(), String> {
+        if self.version() < 7 {
+            return Ok(());
+        }
+
+        let size = self.size();
+        let read = |position: &dyn Fn(u32) -> (u32, u32)| {
+            (0..18).fold(0, |acc, i| {
+                let (x, y) = position(i);
+                acc | (self.dark(x, y) as u32) << i
+            })
+        };
+        let bottom_left = read(&|i| (i / 3, size - 11 + i % 3));
+        let top_right = read(&|i| (size - 11 + i % 3, i / 3));
+
+        for version_bits in [bottom_left, top_right] {
+            if bch_remainder(version_bits, VERSION_GENERATOR) != 0
+                || version_bits >> 12 != self.version() as u32
+            {
+                return Err("Version information is missing or damaged.".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_codewords(&self) -> Result<(), String> {
+        let version = self.version();
+        let ec_level = self.ec_level();
+
+        // module positions come from a fresh template, not from the roles of this symbol
+        let mut template = QrCode::new(version, ec_level, self.mask_pattern(), Encoding::Byte)?;
+        template.all_functional_patterns();
+
+        let size = self.size();
+        let mask_fn = self.mask_pattern().get_mask();
+        let bits: Vec<bool> = template
+            .data_module_indices()
+            .into_iter()
+            .map(|index| {
+                let (x, y) = (index % size, index / size);
+                self.dark(x, y) != mask_fn(x, y)
+            })
+            .collect();
+
+        let codewords: Vec<u8> = bits
+            .chunks_exact(8)
+            .map(|byte| byte.iter().fold(0, |acc, &bit| (acc << 1) | bit as u8))
+            .collect();
+
+        let ec_size = ec_codewords_per_block(version, &ec_level);
+        let blocks = deinterleave(&codewords, version, &ec_level);
+        let total = data_codewords_count(version, &ec_level) + blocks.len() * ec_size;
+        if codewords.len() < total {
+            return Err("Symbol is too small for its codewords.".to_string());
+        }
+
+        for (i, block) in blocks.iter().enumerate() {
+            if syndromes(block, ec_size)
+                .iter()
+                .any(|&syndrome| syndrome != 0)
+            {
+                return Err(format!("Block {} has RS errors.", i));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn bch_remainder(value: u32, generator: u32) -> u32 {
+    let degree = 31 - generator.leading_zeros();
+    let mut remainder = value;
+    while remainder != 0 && 31 - remainder.leading_zeros() >= degree {
+        remainder ^= generator << (31 - remainder.leading_zeros() - degree);
+    }
+    remainder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bit::Bit;
+    use crate::mask::MaskPattern;
+    use crate::preprocessor::Preprocessor;
+
+    fn generate(data: &str, encoding: Encoding, ec_level: EcLevel, mask: MaskPattern) -> QrCode {
+        Preprocessor::new(data, encoding, ec_level, mask).generate_qrcode()
+    }
+
+    fn flip(qrcode: &mut QrCode, x: u32, y: u32) {
+        let index = (x + qrcode.size() * y) as usize;
+        qrcode.data[index] = qrcode.data[index].invert();
+    }
+
+    #[test]
+    fn validate_accepts_generated_symbols() {
+        for mask in MaskPattern::ALL {
+            generate("HELLO WORLD", Encoding::Alphanumeric, EcLevel::Q, mask)
+                .validate()
+                .unwrap();
+        }
+        let long = "https://example.com/".repeat(30);
+        for ec_level in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            generate(&long, Encoding::Byte, ec_level, MaskPattern::Diamonds)
+                .validate()
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn validate_rejects_damaged_finder_pattern() {
+        let mut qrcode = generate(
+            "01234567",
+            Encoding::Numeric,
+            EcLevel::M,
+            MaskPattern::Fields,
+        );
+        flip(&mut qrcode, 3, 3);
+        assert_eq!(
+            qrcode.validate(),
+            Err("Finder pattern at (0, 0) is damaged.".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_rejects_damaged_separator() {
+        let mut qrcode = generate(
+            "01234567",
+            Encoding::Numeric,
+            EcLevel::M,
+            MaskPattern::Fields,
+        );
+        let size = qrcode.size();
+        flip(&mut qrcode, size - 8, 2);
+        assert!(qrcode.validate().unwrap_err().starts_with("Separator"));
+    }
+
+    #[test]
+    fn validate_rejects_mismatching_format_copies() {
+        let mut qrcode = generate(
+            "01234567",
+            Encoding::Numeric,
+            EcLevel::M,
+            MaskPattern::Fields,
+        );
+        flip(&mut qrcode, 0, 8);
+        assert_eq!(
+            qrcode.validate(),
+            Err("Format information copies differ.".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_rejects_missing_version_information() {
+        let long = "https://example.com/".repeat(10);
+        let mut qrcode = generate(&long, Encoding::Byte, EcLevel::L, MaskPattern::Meadow);
+        assert!(qrcode.version() >= 7);
+        let size = qrcode.size();
+        for i in 0..18 {
+            let index = (i / 3 + size * (size - 11 + i % 3)) as usize;
+            qrcode.data[index] = Bit::Zero(qrcode.data[index].role());
+        }
+        assert_eq!(
+            qrcode.validate(),
+            Err("Version information is missing or damaged.".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_rejects_data_errors() {
+        let mut qrcode = generate(
+            "01234567",
+            Encoding::Numeric,
+            EcLevel::M,
+            MaskPattern::Fields,
+        );
+        flip(&mut qrcode, 20, 20);
+        assert_eq!(qrcode.validate(), Err("Block 0 has RS errors.".to_string()));
+    }
+
+    #[test]
+    fn bch_remainder_of_valid_codewords_is_zero() {
+        assert_eq!(bch_remainder(0x77C4 ^ FORMAT_MASK, FORMAT_GENERATOR), 0);
+        assert_eq!(bch_remainder(0x07C94, VERSION_GENERATOR), 0);
+        assert_ne!(bch_remainder(0x07C95, VERSION_GENERATOR), 0);
+    }
+}